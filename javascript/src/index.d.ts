@@ -1,25 +1,124 @@
 export class LucidSuggest {
+    constructor(options?: {lang?: LangCode, langSpec?: string});
     addRecords(records: Record[]): Promise<void>;
+    removeRecords(ids: number[]): Promise<void>;
+    updateRecords(records: Record[]): Promise<void>;
+    exportSnapshot(): Promise<Uint8Array>;
+    importSnapshot(bytes: Uint8Array | ArrayBuffer, records: Record[]): Promise<void>;
+    setDuplicatePolicy(policy: DuplicatePolicy): Promise<void>;
+    addField(name: string, weight?: number): Promise<void>;
+    addLang(lang: LangCode): Promise<void>;
+    setDetectLang(detect: boolean): Promise<void>;
+    setLayoutRetry(minHits: number): Promise<void>;
+    setIncremental(enable: boolean): Promise<void>;
+    setCacheSize(size: number): Promise<void>;
+    cacheStats(): Promise<{hits: number, misses: number}>;
+    setPhonetic(enable: boolean): Promise<void>;
+    setKeyTypos(enable: boolean): Promise<void>;
+    addTransliteration(from: string, to: string): Promise<void>;
+    transliterateCyrillic(): Promise<void>;
     setLimit(limit: number): Promise<void>;
-    search(query: string): Promise<Hit[]>;
+    setFacets(names: string[]): Promise<void>;
+    addSynonyms(group: string[]): Promise<void>;
+    addSynonym(from: string, to: string): Promise<void>;
+    setExplain(explain: boolean): Promise<void>;
+    setRanking(ranking: Ranking): Promise<void>;
+    setMatching(matching: Matching): Promise<void>;
+    search(query: string, options?: SearchOptions): Promise<Hit[]>;
+    searchWithFacets(query: string, options?: SearchOptions): Promise<{hits: Hit[], facets: Facets}>;
 }
 
 
+export type LangCode = 'de' | 'en' | 'es' | 'fr' | 'pt' | 'ru'
+
+
 export interface Record {
-    id:      number,
-    title:   string,
-    rating?: number,
+    id:          number,
+    title:       string,
+    rating?:     number,
+    lang?:       LangCode,
+    attributes?: Attributes,
+    [field: string]: string | number | Attributes | undefined,
+}
+
+
+export type Attributes = {[name: string]: number | boolean | string[]}
+
+
+export type DuplicatePolicy = 'reject' | 'replace' | 'keep_both'
+
+
+export interface SearchOptions {
+    filter?: string,
+    offset?: number,
+    limit?:  number,
+}
+
+
+export type ScoreName =
+    | 'chars' | 'words' | 'tails' | 'trans' | 'fin'
+    | 'offset' | 'rating' | 'word_len' | 'char_len'
+
+
+export interface Ranking {
+    order?:   ScoreName[],
+    weights?: {[score in ScoreName]?: number},
+}
+
+
+export type MatchParam =
+    | 'length_threshold' | 'jaccard_threshold' | 'damlev_threshold'
+    | 'cost_trans' | 'cost_double' | 'cost_vowel' | 'cost_notalpha'
+    | 'cost_consonant' | 'cost_default' | 'cost_neighbor'
+
+
+export interface Matching {
+    typos?:      boolean,
+    prefixOnly?: boolean,
+    params?:     {[param in MatchParam]?: number},
 }
 
 
+export type Facets = {[name: string]: {value: string, count: number}[]}
+
+
 export class Hit {
     title:    string;
     chunks:   HighlightedTextChunk[];
     record:   Record;
+    fields:   {[field: string]: HighlightedTextChunk[]};
+    explain?: Explanation;
+    correction?: string;
     position: number;
 }
 
 
+export interface Explanation {
+    scores:     {[score in ScoreName]: number},
+    rmatches:   MatchExplanation[],
+    qmatches:   MatchExplanation[],
+    fields:     {[field: string]: FieldExplanation},
+    grams:      number,
+    candidates: number,
+}
+
+
+export interface FieldExplanation {
+    weight:   number,
+    rmatches: MatchExplanation[],
+    qmatches: MatchExplanation[],
+}
+
+
+export interface MatchExplanation {
+    word:  string,
+    len:   number,
+    typos: number,
+    func:  boolean,
+    fin:   boolean,
+}
+
+
 export type HighlightedTextChunk = {
     text:      string,
     highlight: boolean,
@@ -1,19 +1,28 @@
 import compileWasm from '../pkg/lucid_suggest_wasm'
 
 const DEFAULT_LIMIT = 10
+const DEFAULT_RANKING = [
+    'chars', 'words', 'tails', 'trans', 'fin',
+    'offset', 'rating', 'word_len', 'char_len',
+]
 var NEXT_ID = 1
 
 
 export class LucidSuggest {
-    constructor() {
+    constructor({lang = '', langSpec} = {}) {
         this.id         = NEXT_ID++
         this.limit      = DEFAULT_LIMIT
         this.records    = new Map()
+        this.fields     = []
+        this.langs      = new Map([[lang, 0]])
         this.setupQueue = compileWasm
 
         this.setup(wasm => {
-            wasm.create_store(this.id)
-            wasm.highlight_with(this.id, '{{', '}}')
+            if (langSpec === undefined) {
+                wasm.create_store(this.id, lang)
+            } else {
+                wasm.create_store_with_spec(this.id, langSpec)
+            }
         })
     }
 
@@ -36,37 +45,273 @@ export class LucidSuggest {
 
     addRecords(records) {
         return this.setup(wasm => {
+            let duplicate = null
             for (const record of records) {
                 const {id, title, rating} = record
-                wasm.add_record(this.id, id, title, rating || 0)
+                const fields = this.joinFields(record)
+                try {
+                    wasm.add_record(this.id, id, title, fields, rating || 0, this.langIndex(record))
+                } catch (err) {
+                    // Rejected duplicates don't stop the other records.
+                    if (err.code !== 'DUPLICATE_RECORD') throw err
+                    duplicate = duplicate || err
+                    continue
+                }
+                this.setAttributes(wasm, record)
                 this.records.set(id, record)
             }
+            if (duplicate) throw duplicate
+        })
+    }
+
+    removeRecords(ids) {
+        return this.setup(wasm => {
+            let unknown = null
+            for (const id of ids) {
+                try {
+                    wasm.remove_record(this.id, id)
+                } catch (err) {
+                    // Unknown ids don't stop the other records.
+                    if (err.code !== 'UNKNOWN_RECORD') throw err
+                    unknown = unknown || err
+                    continue
+                }
+                this.records.delete(id)
+            }
+            if (unknown) throw unknown
+        })
+    }
+
+    updateRecords(records) {
+        return this.setup(wasm => {
+            let unknown = null
+            for (const record of records) {
+                const {id, title, rating} = record
+                try {
+                    wasm.update_record(this.id, id, title, this.joinFields(record), rating || 0, this.langIndex(record))
+                } catch (err) {
+                    // Unknown ids don't stop the other records.
+                    if (err.code !== 'UNKNOWN_RECORD') throw err
+                    unknown = unknown || err
+                    continue
+                }
+                this.setAttributes(wasm, record)
+                this.records.set(id, record)
+            }
+            if (unknown) throw unknown
+        })
+    }
+
+    addLang(lang) {
+        return this.setup(wasm => {
+            if (this.langs.has(lang)) return
+            this.langs.set(lang, wasm.add_lang(this.id, lang))
+        })
+    }
+
+    setDetectLang(detect) {
+        return this.setup(wasm => {
+            wasm.set_detect_lang(this.id, detect)
+        })
+    }
+
+    setLayoutRetry(minHits) {
+        return this.setup(wasm => {
+            wasm.set_layout_retry(this.id, minHits)
+        })
+    }
+
+    setIncremental(enable) {
+        return this.setup(wasm => {
+            wasm.set_incremental(this.id, enable)
+        })
+    }
+
+    setCacheSize(size) {
+        return this.setup(wasm => {
+            wasm.set_cache_size(this.id, size)
+        })
+    }
+
+    async cacheStats() {
+        const wasm = await this.setupQueue
+        const [hits, misses] = wasm.get_cache_stats(this.id)
+        return {hits, misses}
+    }
+
+    setPhonetic(enable) {
+        return this.setup(wasm => {
+            wasm.set_phonetic(this.id, enable)
+        })
+    }
+
+    setKeyTypos(enable) {
+        return this.setup(wasm => {
+            wasm.set_key_typos(this.id, enable)
+        })
+    }
+
+    addTransliteration(from, to) {
+        return this.setup(wasm => {
+            wasm.add_transliteration(this.id, from, to)
+        })
+    }
+
+    transliterateCyrillic() {
+        return this.setup(wasm => {
+            wasm.transliterate_cyrillic(this.id)
+        })
+    }
+
+    langIndex({lang}) {
+        if (lang === undefined) return undefined
+        if (!this.langs.has(lang)) {
+            throw new Error(`Unknown record language ${lang}, call addLang first`)
+        }
+        return this.langs.get(lang)
+    }
+
+    addField(name, weight = 1) {
+        return this.setup(wasm => {
+            wasm.add_field(this.id, name, weight)
+            this.fields.push(name)
+        })
+    }
+
+    joinFields(record) {
+        return this.fields.map(name => record[name] || '').join('\0')
+    }
+
+    setAttributes(wasm, record) {
+        const {id, attributes} = record
+        for (const [name, value] of Object.entries(attributes || {})) {
+            if (Array.isArray(value)) {
+                wasm.set_keywords_attr(this.id, id, name, value.join('\0'))
+            } else {
+                wasm.set_int_attr(this.id, id, name, Number(value))
+            }
+        }
+    }
+
+    async exportSnapshot() {
+        const wasm = await this.setupQueue
+        return wasm.export_store(this.id)
+    }
+
+    importSnapshot(bytes, records) {
+        return this.setup(wasm => {
+            wasm.import_store(this.id, new Uint8Array(bytes))
+            this.records.clear()
+            for (const record of records) {
+                this.records.set(record.id, record)
+            }
+        })
+    }
+
+    setDuplicatePolicy(policy) {
+        return this.setup(wasm => {
+            wasm.set_duplicate_policy(this.id, policy)
         })
     }
 
     setLimit(limit) {
         return this.setup(wasm => {
+            wasm.set_limit(this.id, limit)
             this.limit = limit
-            wasm.set_limit(this.id, this.limit)
         })
     }
 
-    async search(query) {
+    setRanking({order = DEFAULT_RANKING, weights = {}}) {
+        return this.setup(wasm => {
+            const weighted = Object.keys(weights)
+            wasm.set_ranking(
+                this.id,
+                order.join('\0'),
+                weighted.join('\0'),
+                new Float64Array(weighted.map(name => weights[name])),
+            )
+        })
+    }
+
+    setMatching({typos = true, prefixOnly = false, params = {}}) {
+        return this.setup(wasm => {
+            const names = Object.keys(params)
+            wasm.set_matching(
+                this.id,
+                typos,
+                prefixOnly,
+                names.join('\0'),
+                new Float64Array(names.map(name => params[name])),
+            )
+        })
+    }
+
+    setExplain(explain) {
+        return this.setup(wasm => {
+            wasm.set_explain(this.id, explain)
+        })
+    }
+
+    setFacets(names) {
+        return this.setup(wasm => {
+            wasm.set_facets(this.id, names.join('\0'))
+        })
+    }
+
+    addSynonyms(group) {
+        return this.setup(wasm => {
+            wasm.add_synonyms(this.id, group.join('\0'))
+        })
+    }
+
+    addSynonym(from, to) {
+        return this.setup(wasm => {
+            wasm.add_synonym(this.id, from, to)
+        })
+    }
+
+    async search(query, options = {}) {
+        const {hits} = await this.searchWithFacets(query, options)
+        return hits
+    }
+
+    async searchWithFacets(query, {filter = '', offset = 0, limit} = {}) {
         const wasm = await this.setupQueue
-        wasm.run_search(this.id, query)
-        const ids    = wasm.get_result_ids(this.id)
-        const titles = wasm.get_result_titles(this.id).split('\0')
-        const hits   = []
+        wasm.run_search(this.id, query, filter, offset, limit === undefined ? this.limit : limit)
+        const ids        = wasm.get_result_ids(this.id)
+        const spans      = new SpanReader(wasm.get_result_spans(this.id))
+        const fieldSpans = new SpanReader(wasm.get_result_field_spans(this.id))
+        const explanations = JSON.parse(wasm.get_result_explanations(this.id))
+        const corrections  = wasm.get_result_corrections(this.id).split('\0')
+        const hits       = []
         for (let i = 0; i < ids.length; i++) {
             const id     = ids[i]
-            const title  = titles[i]
             const record = this.records.get(id)
             if (!record) throw new Error(`Missing record ${id}`)
-            if (!title)  throw new Error(`Missing title for ${id}`)
-            hits.push(new Hit(title, record))
+            const hit = new Hit(toChunks(record.title, spans.next()), record)
+            for (const name of this.fields) {
+                hit.fields[name] = toChunks(record[name] || '', fieldSpans.next())
+            }
+            if (explanations[i]) hit.explain = explanations[i]
+            if (corrections[i]) hit.correction = corrections[i]
+            hits.push(hit)
         }
-        return hits
+        return {hits, facets: getFacets(wasm, this.id)}
+    }
+}
+
+
+function getFacets(wasm, id) {
+    const names  = wasm.get_facet_names(id).split('\0')
+    const values = wasm.get_facet_values(id).split('\0')
+    const counts = wasm.get_facet_counts(id)
+    const facets = {}
+    for (let i = 0; i < counts.length; i++) {
+        const name = names[i]
+        if (!facets[name]) facets[name] = []
+        facets[name].push({value: values[i], count: counts[i]})
     }
+    return facets
 }
 
 
@@ -82,9 +327,10 @@ export function highlight(hit, left, right) {
 
 
 export class Hit {
-    constructor(title, record) {
+    constructor(chunks, record) {
         this.record = record
-        this.chunks = toChunks(title)
+        this.chunks = chunks
+        this.fields = {}
     }
 
     get title() {
@@ -93,16 +339,36 @@ export class Hit {
 }
 
 
-function toChunks(title) {
-    const split  = title.split(/{{|}}/g)
+// Reads spans encoded as a count followed by start, end pairs.
+class SpanReader {
+    constructor(buffer) {
+        this.buffer = buffer
+        this.offset = 0
+    }
+
+    next() {
+        const count = this.buffer[this.offset++]
+        const spans = []
+        for (let i = 0; i < count; i++) {
+            spans.push([this.buffer[this.offset++], this.buffer[this.offset++]])
+        }
+        return spans
+    }
+}
+
+
+function toChunks(text, spans) {
     const chunks = []
-    for (let i = 0; i < split.length; i++) {
-        if (split[i] != '') {
-            chunks.push({
-                text: split[i],
-                highlight: i % 2 === 1,
-            })
+    let offset = 0
+    for (const [start, end] of spans) {
+        if (start > offset) {
+            chunks.push({text: text.slice(offset, start), highlight: false})
         }
+        chunks.push({text: text.slice(start, end), highlight: true})
+        offset = end
+    }
+    if (offset < text.length) {
+        chunks.push({text: text.slice(offset), highlight: false})
     }
     return chunks
 }
@@ -89,6 +89,274 @@ describe('Suggest', () => {
         expect(hits).toMatchSnapshot()
     })
 
+    test('Remove records', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addRecords(records)
+        suggest.removeRecords([20])
+        const hits = await suggest.search('ba')
+        expect(hits.map(h => h.record.id)).toEqual([30])
+    })
+
+    test('Update records', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addRecords(records)
+        suggest.updateRecords([{id: 10, title: 'Goodbye world!'}])
+        const hits1 = await suggest.search('hello')
+        const hits2 = await suggest.search('goodbye')
+        expect(hits1).toHaveLength(0)
+        expect(hits2.map(h => h.record.title)).toEqual(['Goodbye world!'])
+    })
+
+    test('Snapshot', async () => {
+        const suggest1 = new LucidSuggest()
+        suggest1.addRecords(records)
+        const bytes = await suggest1.exportSnapshot()
+
+        const suggest2 = new LucidSuggest()
+        suggest2.importSnapshot(bytes, records)
+        const hits1 = await suggest1.search('ba')
+        const hits2 = await suggest2.search('ba')
+        expect(hits2).toEqual(hits1)
+    })
+
+    test('Fields', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addField('brand', 0.5)
+        suggest.addRecords([
+            {id: 1, title: 'Wireless router', brand: 'Netgear'},
+            {id: 2, title: 'USB cable',       brand: 'Belkin'},
+        ])
+        const hits = await suggest.search('netgear')
+        expect(hits.map(h => h.record.id)).toEqual([1])
+        expect(hits[0].fields.brand).toEqual([{text: 'Netgear', highlight: true}])
+    })
+
+    test('Fields missing in records', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addRecords([{id: 1, title: 'Netgear router', brand: 'Netgear'}])
+        suggest.addField('brand')
+        suggest.addRecords([{id: 2, title: 'Wireless router', brand: 'Netgear'}])
+        const hits = await suggest.search('netgear router')
+        expect(hits.map(h => h.record.id)).toEqual([1, 2])
+        expect(hits[0].fields.brand).toEqual([{text: 'Netgear', highlight: false}])
+        expect(hits[1].fields.brand).toEqual([{text: 'Netgear', highlight: true}])
+    })
+
+    test('Filter', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setLimit(2)
+        suggest.addRecords([
+            {id: 1, title: 'Running shoes', attributes: {inStock: true,  size: 42, tags: ['sport']}},
+            {id: 2, title: 'Tennis shoes',  attributes: {inStock: false, size: 44, tags: ['sport']}},
+            {id: 3, title: 'Office shoes',  attributes: {inStock: true,  size: 44}},
+            {id: 4, title: 'Beach shoes',   attributes: {inStock: true,  size: 40, tags: ['summer']}},
+        ])
+        const hits1 = await suggest.search('shoes', {filter: 'inStock = 1 & size > 40'})
+        const hits2 = await suggest.search('shoes', {filter: '!tags : sport'})
+        expect(hits1.map(h => h.record.id).sort()).toEqual([1, 3])
+        expect(hits2.map(h => h.record.id).sort()).toEqual([3, 4])
+    })
+
+    test('Facets', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setLimit(1)
+        suggest.setFacets(['category'])
+        suggest.addRecords([
+            {id: 1, title: 'Running shoes', attributes: {category: ['shoes']}},
+            {id: 2, title: 'Tennis shoes',  attributes: {category: ['shoes', 'sport']}},
+            {id: 3, title: 'Shoes bag',     attributes: {category: ['bags']}},
+        ])
+        const {hits, facets} = await suggest.searchWithFacets('shoes')
+        expect(hits.length).toEqual(1)
+        expect(facets).toEqual({
+            category: [
+                {value: 'shoes', count: 2},
+                {value: 'bags',  count: 1},
+                {value: 'sport', count: 1},
+            ],
+        })
+    })
+
+    test('Lang spec', async () => {
+        const suggest = new LucidSuggest({langSpec: 'stemmer english\narticle the\nreduce ß ss'})
+        suggest.addRecords([
+            {id: 1, title: 'The Passstraße'},
+        ])
+        const hits = await suggest.search('passstrasse')
+        expect(hits.map(hit => highlight(hit, '[', ']'))).toEqual(['The [Passstraße]'])
+        const invalid = new LucidSuggest({langSpec: 'stemmer klingon'})
+        await expect(invalid.search('passstrasse')).rejects.toThrow('Unknown stemmer')
+    })
+
+    test('Errors', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setLimit(0)
+        await expect(suggest.search('foo')).rejects.toThrow('Invalid limit 0')
+        await expect(suggest.search('foo')).rejects.toMatchObject({name: 'StoreError', code: 'INVALID_LIMIT'})
+        const policy = new LucidSuggest()
+        policy.setDuplicatePolicy('ignore')
+        await expect(policy.search('foo')).rejects.toThrow('Unknown duplicate policy')
+        await expect(policy.search('foo')).rejects.toMatchObject({code: 'INVALID_ARGUMENT'})
+        const duplicates = new LucidSuggest()
+        duplicates.setDuplicatePolicy('reject')
+        duplicates.addRecords([{id: 1, title: 'Foo'}, {id: 1, title: 'Bar'}, {id: 2, title: 'Foo bar'}])
+        await expect(duplicates.search('foo')).rejects.toMatchObject({name: 'StoreError', code: 'DUPLICATE_RECORD'})
+        expect([...duplicates.records.keys()]).toEqual([1, 2])
+        const updated = new LucidSuggest()
+        updated.addRecords([{id: 1, title: 'Foo'}, {id: 2, title: 'Bar'}])
+        updated.updateRecords([{id: 3, title: 'Baz'}, {id: 1, title: 'Foo bar'}])
+        await expect(updated.search('foo')).rejects.toMatchObject({name: 'StoreError', code: 'UNKNOWN_RECORD'})
+        expect(updated.records.get(1).title).toEqual('Foo bar')
+        const removed = new LucidSuggest()
+        removed.addRecords([{id: 1, title: 'Foo'}, {id: 2, title: 'Bar'}])
+        removed.removeRecords([3, 2])
+        await expect(removed.search('foo')).rejects.toMatchObject({name: 'StoreError', code: 'UNKNOWN_RECORD'})
+        expect([...removed.records.keys()]).toEqual([1])
+    })
+
+    test('Synonyms', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addSynonyms(['tv', 'television'])
+        suggest.addRecords([
+            {id: 1, title: 'Smart Television 55in'},
+            {id: 2, title: 'Television stand'},
+        ])
+        const hits = await suggest.search('tv')
+        expect(hits.map(hit => highlight(hit, '[', ']')).sort()).toEqual([
+            'Smart [Television] 55in',
+            '[Television] stand',
+        ])
+    })
+
+    test('Phonetic', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setPhonetic(true)
+        suggest.addRecords([
+            {id: 1, title: 'John Smith'},
+            {id: 2, title: 'Jack Smart'},
+        ])
+        const hits = await suggest.search('jon smyth')
+        expect(hits.map(hit => highlight(hit, '[', ']'))).toEqual(['[John] [Smith]'])
+    })
+
+    test('Key typos', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addRecords([{id: 1, title: 'Cat toy'}])
+        const hits1 = await suggest.search('vat')
+        suggest.setKeyTypos(true)
+        const hits2 = await suggest.search('vat')
+        expect(hits1).toEqual([])
+        expect(hits2.map(hit => highlight(hit, '[', ']'))).toEqual(['[Cat] toy'])
+    })
+
+    test('Ranking', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addRecords([
+            {id: 1, title: 'Metal mailbox',        rating: 10},
+            {id: 2, title: 'Yellow metal mailbox', rating: 20},
+            {id: 3, title: 'Mailbox of metal',     rating: 30},
+        ])
+        const hits1 = await suggest.search('metal mailbox')
+        suggest.setRanking({order: ['chars', 'rating']})
+        const hits2 = await suggest.search('metal mailbox')
+        expect(hits1.map(h => h.record.id)).toEqual([1, 2, 3])
+        expect(hits2.map(h => h.record.id)).toEqual([3, 2, 1])
+    })
+
+    test('Matching', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addRecords([
+            {id: 1, title: 'Metal mailbox'},
+        ])
+        const hits1 = await suggest.search('metl')
+        suggest.setMatching({typos: false})
+        const hits2 = await suggest.search('metl')
+        suggest.setMatching({prefixOnly: true})
+        const hits3 = await suggest.search('met mail')
+        expect(hits1.map(h => h.record.id)).toEqual([1])
+        expect(hits2.map(h => h.record.id)).toEqual([])
+        expect(hits3.map(hit => highlight(hit, '[', ']'))).toEqual(['[Met]al [mail]box'])
+    })
+
+    test('Incremental', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setIncremental(true)
+        suggest.addRecords([
+            {id: 1, title: 'Electric toothbrush'},
+            {id: 2, title: 'Elegant dress'},
+        ])
+        const hits1 = await suggest.search('ele')
+        const hits2 = await suggest.search('elec')
+        const hits3 = await suggest.search('elec dress')
+        expect(hits1.map(h => h.record.id)).toEqual([2, 1])
+        expect(hits2.map(h => h.record.id)).toEqual([1])
+        expect(hits3.map(h => h.record.id).sort()).toEqual([1, 2])
+    })
+
+    test('Cache', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setCacheSize(10)
+        suggest.addRecords([
+            {id: 1, title: 'Electric toothbrush'},
+        ])
+        const hits1 = await suggest.search('elec')
+        const hits2 = await suggest.search('Elec')
+        expect(hits2.map(h => h.record.id)).toEqual(hits1.map(h => h.record.id))
+        expect(await suggest.cacheStats()).toEqual({hits: 1, misses: 1})
+    })
+
+    test('Explain', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setExplain(true)
+        suggest.addRecords([
+            {id: 1, title: 'Yellow metal mailbox'},
+        ])
+        const [hit] = await suggest.search('metl mail')
+        expect(hit.explain.scores.chars).toEqual(7)
+        expect(hit.explain.rmatches[0].word).toEqual('metal')
+        expect(hit.explain.qmatches[0]).toEqual({word: 'metl', len: 4, typos: 1, func: false, fin: true})
+        expect(hit.explain.candidates).toEqual(1)
+    })
+
+    test('Explain fields', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setExplain(true)
+        suggest.addField('brand', 2)
+        suggest.addRecords([
+            {id: 1, title: 'Yellow metal mailbox', brand: 'Acme'},
+        ])
+        const [hit] = await suggest.search('mailbox acme')
+        expect(hit.explain.fields.brand.weight).toEqual(2)
+        expect(hit.explain.fields.brand.rmatches[0].word).toEqual('Acme')
+        expect(hit.explain.fields.brand.qmatches[0]).toEqual({word: 'acme', len: 4, typos: 0, func: false, fin: true})
+    })
+
+    test('Pages', async () => {
+        const suggest = new LucidSuggest()
+        suggest.setLimit(2)
+        suggest.addRecords(records)
+        const all   = await suggest.search('', {limit: records.length})
+        const page1 = await suggest.search('')
+        const page2 = await suggest.search('', {offset: 2})
+        const page3 = await suggest.search('', {offset: 3, limit: 10})
+        expect(page1).toEqual(all.slice(0, 2))
+        expect(page2).toEqual(all.slice(2, 4))
+        expect(page3).toEqual(all.slice(3))
+    })
+
+    test('Highlight braces', async () => {
+        const suggest = new LucidSuggest()
+        suggest.addRecords([
+            {id: 1, title: '{{Mustache}} templates'},
+        ])
+        const [hit] = await suggest.search('templ')
+        expect(hit.chunks).toEqual([
+            {text: '{{Mustache}} ', highlight: false},
+            {text: 'templ',         highlight: true},
+            {text: 'ates',          highlight: false},
+        ])
+    })
+
     describe('Render', () => {
         test('highlight helper', async () => {
             const suggest = new LucidSuggest()
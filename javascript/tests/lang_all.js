@@ -0,0 +1,62 @@
+const {LucidSuggest, highlight} = require('../all')
+
+
+describe('All languages bundle', () => {
+    const render = hits => hits.map(hit => highlight(hit, '[', ']'))
+
+    test('Stores with different languages', async () => {
+        const en = new LucidSuggest({lang: 'en'})
+        const ru = new LucidSuggest({lang: 'ru'})
+        en.addRecords([{id: 1, title: 'Electric toothbrush'}])
+        ru.addRecords([{id: 1, title: 'Электрическая зубная щётка'}])
+        expect(render(await en.search('electric'))).toEqual(['[Electric] toothbrush'])
+        expect(render(await ru.search('зубной'))).toEqual(['Электрическая [зубн]ая щётка'])
+    })
+
+    test('Records in different languages', async () => {
+        const suggest = new LucidSuggest({lang: 'en'})
+        suggest.addLang('ru')
+        suggest.addRecords([
+            {id: 1, title: 'Electric toothbrush'},
+            {id: 2, title: 'Электрическая зубная щётка', lang: 'ru'},
+        ])
+        expect(render(await suggest.search('зубной'))).toEqual(['Электрическая [зубн]ая щётка'])
+        expect(render(await suggest.search('electric'))).toEqual(['[Electric] toothbrush'])
+    })
+
+    test('Detected languages', async () => {
+        const suggest = new LucidSuggest({lang: 'en'})
+        suggest.addLang('ru')
+        suggest.setDetectLang(true)
+        suggest.addRecords([
+            {id: 1, title: 'Electric toothbrush'},
+            {id: 2, title: 'Электрическая зубная щётка'},
+        ])
+        expect(render(await suggest.search('зубной'))).toEqual(['Электрическая [зубн]ая щётка'])
+    })
+
+    test('Keyboard layout retry', async () => {
+        const suggest = new LucidSuggest({lang: 'ru'})
+        suggest.setLayoutRetry(1)
+        suggest.addRecords([{id: 1, title: 'Коврик для мыши'}])
+        const hits = await suggest.search('rjdhbr')
+        expect(render(hits)).toEqual(['[Коврик] для мыши'])
+        expect(hits[0].correction).toEqual('коврик')
+    })
+
+    test('Transliteration', async () => {
+        const suggest = new LucidSuggest({lang: 'ru'})
+        suggest.transliterateCyrillic()
+        suggest.addRecords([
+            {id: 1, title: 'Samsung Galaxy'},
+            {id: 2, title: 'Чехол Самсунг'},
+        ])
+        expect(render(await suggest.search('самсунг'))).toEqual(['[Samsung] Galaxy', 'Чехол [Самсунг]'])
+        expect(render(await suggest.search('чех'))).toEqual(['[Чех]ол Самсунг'])
+    })
+
+    test('Unknown language', async () => {
+        const suggest = new LucidSuggest({lang: 'xx'})
+        await expect(suggest.search('electric')).rejects.toThrow('Unknown language')
+    })
+})
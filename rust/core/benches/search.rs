@@ -17,7 +17,7 @@ fn search_benchmark(criterion: &mut Criterion) {
                 let mut i = 0;
                 bench.iter(|| {
                     let query = &queries[i].to_ref();
-                    store.search(black_box(query));
+                    store.search(black_box(query), None);
                     i = (i + 1) % queries.len();
                 })
             });
@@ -25,7 +25,28 @@ fn search_benchmark(criterion: &mut Criterion) {
     }
 }
 
-criterion_group!(benches, search_benchmark);
+/// Stores where candidate counting and scoring dominate, compare with `--features parallel`.
+fn search_large_benchmark(criterion: &mut Criterion) {
+    let dataset   = SyntheticDataset::new();
+    let mut group = criterion.benchmark_group("search large");
+    group.sample_size(20);
+
+    for &n_records in &[100_000, 300_000] {
+        let (store, queries) = dataset.gen_data(n_records, 4, 8);
+        let bench_name = format!("search {} records, 4-8 words", n_records);
+        group.bench_function(&bench_name, |bench| {
+            let mut i = 0;
+            bench.iter(|| {
+                let query = &queries[i].to_ref();
+                store.search(black_box(query), None);
+                i = (i + 1) % queries.len();
+            })
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, search_benchmark, search_large_benchmark);
 criterion_main!(benches);
 
 
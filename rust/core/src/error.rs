@@ -0,0 +1,51 @@
+use std::fmt;
+use std::error::Error;
+use crate::store::SnapshotError;
+
+
+/// Failure of a call to the store API in the crate root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StoreError {
+    UnknownStore(usize),
+    DuplicateStore(usize),
+    /// Record id already used, with `DuplicatePolicy::Reject`.
+    DuplicateRecord(usize),
+    /// No record with the id to remove or update.
+    UnknownRecord(usize),
+    InvalidLimit(usize),
+    /// Record language index not returned by `add_lang`.
+    UnknownLang(usize),
+    Snapshot(SnapshotError),
+}
+
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::UnknownStore(id)    => write!(f, "Unknown store id {}", id),
+            StoreError::DuplicateStore(id)  => write!(f, "Duplicate store id {}", id),
+            StoreError::DuplicateRecord(id) => write!(f, "Duplicate record id {}", id),
+            StoreError::UnknownRecord(id)   => write!(f, "Unknown record id {}", id),
+            StoreError::InvalidLimit(limit) => write!(f, "Invalid limit {}", limit),
+            StoreError::UnknownLang(ix)     => write!(f, "Unknown language index {}", ix),
+            StoreError::Snapshot(err)       => err.fmt(f),
+        }
+    }
+}
+
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::Snapshot(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+
+impl From<SnapshotError> for StoreError {
+    fn from(err: SnapshotError) -> Self {
+        StoreError::Snapshot(err)
+    }
+}
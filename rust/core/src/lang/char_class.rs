@@ -31,6 +31,36 @@ use CharClass::{
 };
 
 
+impl CharClass {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Any         => "any",
+            Control     => "control",
+            Whitespace  => "whitespace",
+            Punctuation => "punctuation",
+            NotAlpha    => "not_alpha",
+            NotAlphaNum => "not_alpha_num",
+            Consonant   => "consonant",
+            Vowel       => "vowel",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "any"           => Some(Any),
+            "control"       => Some(Control),
+            "whitespace"    => Some(Whitespace),
+            "punctuation"   => Some(Punctuation),
+            "not_alpha"     => Some(NotAlpha),
+            "not_alpha_num" => Some(NotAlphaNum),
+            "consonant"     => Some(Consonant),
+            "vowel"         => Some(Vowel),
+            _ => None,
+        }
+    }
+}
+
+
 impl CharPattern for CharClass {
     fn matches(&self, ch: char, lang: &Lang) -> Option<bool> {
         match self {
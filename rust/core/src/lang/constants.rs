@@ -29,3 +29,39 @@ pub const CHAR_CLASSES_LATIN: &[(CharClass, char)] = &[
     (Vowel, 'u'),
     (Vowel, 'y'),
 ];
+
+
+/// Soft and hard signs have no Latin letter and are left untransliterated, as a char can't map to nothing.
+pub const CYRILLIC_TO_LATIN: &[(&str, &str)] = &[
+    ("а", "a"),
+    ("б", "b"),
+    ("в", "v"),
+    ("г", "g"),
+    ("д", "d"),
+    ("е", "e"),
+    ("ё", "e"),
+    ("ж", "zh"),
+    ("з", "z"),
+    ("и", "i"),
+    ("й", "y"),
+    ("к", "k"),
+    ("л", "l"),
+    ("м", "m"),
+    ("н", "n"),
+    ("о", "o"),
+    ("п", "p"),
+    ("р", "r"),
+    ("с", "s"),
+    ("т", "t"),
+    ("у", "u"),
+    ("ф", "f"),
+    ("х", "kh"),
+    ("ц", "ts"),
+    ("ч", "ch"),
+    ("ш", "sh"),
+    ("щ", "shch"),
+    ("ы", "y"),
+    ("э", "e"),
+    ("ю", "yu"),
+    ("я", "ya"),
+];
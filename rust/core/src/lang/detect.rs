@@ -0,0 +1,110 @@
+use std::sync::OnceLock;
+use fnv::{FnvHashMap as HashMap};
+use super::PartOfSpeech;
+use super::{lang_english, lang_french, lang_german, lang_portuguese, lang_spanish};
+
+
+type FunctionWords = &'static [(PartOfSpeech, &'static str)];
+
+
+/// Latin script languages with their function words and chars barely used by the others.
+const LATIN_PROFILES: &[(&str, FunctionWords, &str)] = &[
+    ("de", lang_german::FUNCTION_WORDS,     "äöüß"),
+    ("en", lang_english::FUNCTION_WORDS,    ""),
+    ("es", lang_spanish::FUNCTION_WORDS,    "ñ¿¡"),
+    ("fr", lang_french::FUNCTION_WORDS,     "àâæèêëîïœùûÿ"),
+    ("pt", lang_portuguese::FUNCTION_WORDS, "ãõ"),
+];
+
+const CYRILLIC_LANG: &str = "ru";
+
+static FUNCTION_WORD_LANGS: OnceLock<HashMap<&'static str, Vec<usize>>> = OnceLock::new();
+
+
+/// Guesses a language code (`de`, `en`, `es`, `fr`, `pt` or `ru`) by script,
+/// then by counting function words and distinctive chars.
+/// Returns `None` when there is no evidence or the leaders are tied.
+pub fn detect_lang(text: &str) -> Option<&'static str> {
+    let mut latin    = 0;
+    let mut cyrillic = 0;
+    for ch in text.chars() {
+        match ch {
+            'a' ..= 'z' | 'A' ..= 'Z' | '\u{00C0}' ..= '\u{024F}' => latin += 1,
+            '\u{0400}' ..= '\u{04FF}' => cyrillic += 1,
+            _ => {},
+        }
+    }
+    if cyrillic > latin {
+        return Some(CYRILLIC_LANG);
+    }
+    if latin == 0 {
+        return None;
+    }
+
+    let lowercase = text.to_lowercase();
+    let mut scores = [0; LATIN_PROFILES.len()];
+
+    let langs = FUNCTION_WORD_LANGS.get_or_init(collect_function_words);
+    let words = lowercase.split(|ch: char| !ch.is_alphabetic() && ch != '\'');
+    for word in words {
+        for &lang_ix in langs.get(word).into_iter().flatten() {
+            scores[lang_ix] += 2;
+        }
+    }
+    for ch in lowercase.chars() {
+        for (lang_ix, (_, _, chars)) in LATIN_PROFILES.iter().enumerate() {
+            if chars.contains(ch) {
+                scores[lang_ix] += 1;
+            }
+        }
+    }
+
+    let best = *scores.iter().max()?;
+    if best == 0 || scores.iter().filter(|&&score| score == best).count() > 1 {
+        return None;
+    }
+    let lang_ix = scores.iter().position(|&score| score == best)?;
+    Some(LATIN_PROFILES[lang_ix].0)
+}
+
+
+fn collect_function_words() -> HashMap<&'static str, Vec<usize>> {
+    let mut langs: HashMap<&'static str, Vec<usize>> = HashMap::default();
+    for (lang_ix, (_, words, _)) in LATIN_PROFILES.iter().enumerate() {
+        for &(_, word) in words.iter() {
+            let word_langs = langs.entry(word).or_default();
+            if !word_langs.contains(&lang_ix) {
+                word_langs.push(lang_ix);
+            }
+        }
+    }
+    langs
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::detect_lang;
+
+    #[test]
+    fn detect_lang_script() {
+        assert_eq!(detect_lang("Электрическая зубная щётка"), Some("ru"));
+        assert_eq!(detect_lang("USB кабель"),                 Some("ru"));
+        assert_eq!(detect_lang("42"),                         None);
+    }
+
+    #[test]
+    fn detect_lang_function_words() {
+        assert_eq!(detect_lang("The lord of the rings"),      Some("en"));
+        assert_eq!(detect_lang("Der Herr der Ringe"),         Some("de"));
+        assert_eq!(detect_lang("El señor de los anillos"),    Some("es"));
+        assert_eq!(detect_lang("Le seigneur des anneaux"),    Some("fr"));
+        assert_eq!(detect_lang("O senhor com o anel"),        Some("pt"));
+    }
+
+    #[test]
+    fn detect_lang_chars() {
+        assert_eq!(detect_lang("Mitteltöner"), Some("de"));
+        assert_eq!(detect_lang("Toothbrush"),  None);
+    }
+}
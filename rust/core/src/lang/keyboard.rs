@@ -0,0 +1,151 @@
+/// Physical keyboard layout, used to fix queries typed with a wrong layout active.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyboardLayout {
+    Qwerty,
+    Qwertz,
+    Azerty,
+    Jcuken,
+}
+
+use KeyboardLayout::{
+    Qwerty,
+    Qwertz,
+    Azerty,
+    Jcuken,
+};
+
+
+// Letter rows key by key, plus the key left to 1, so chars at the same position share a key.
+const KEYS_QWERTY: &str = "`qwertyuiop[]\\asdfghjkl;'zxcvbnm,./";
+const KEYS_QWERTZ: &str = "^qwertzuiopü+#asdfghjklöäyxcvbnm,.-";
+const KEYS_AZERTY: &str = "²azertyuiop^$*qsdfghjklmùwxcvbn,;:!";
+const KEYS_JCUKEN: &str = "ёйцукенгшщзхъ\\фывапролджэячсмитьбю.";
+
+// Lengths of the letter rows and their shifts to the right, in key widths.
+const ROWS: [(usize, f64); 3] = [(13, 0.0), (11, 0.25), (10, 0.75)];
+
+
+impl KeyboardLayout {
+    fn keys(&self) -> &'static str {
+        match self {
+            Qwerty => KEYS_QWERTY,
+            Qwertz => KEYS_QWERTZ,
+            Azerty => KEYS_AZERTY,
+            Jcuken => KEYS_JCUKEN,
+        }
+    }
+
+    /// Pairs of letter keys, in both orders, with centers closer than `max_dist` key widths.
+    pub fn key_distances(&self, max_dist: f64) -> Vec<(char, char, f64)> {
+        let mut keys  = Vec::new();
+        let mut chars = self.keys().chars().skip(1);
+        for (y, &(len, shift)) in ROWS.iter().enumerate() {
+            for (x, ch) in chars.by_ref().take(len).enumerate() {
+                keys.push((ch, x as f64 + shift, y as f64));
+            }
+        }
+        let mut dists = Vec::new();
+        for &(ch1, x1, y1) in &keys {
+            for &(ch2, x2, y2) in &keys {
+                let dist = ((x1 - x2).powi(2) + (y1 - y2).powi(2)).sqrt();
+                if ch1 != ch2 && dist < max_dist {
+                    dists.push((ch1, ch2, dist));
+                }
+            }
+        }
+        dists
+    }
+
+    /// Text as if typed on the same keys with `target` layout active, keeping case.
+    /// Returns `None` if nothing changes.
+    pub fn convert(&self, target: KeyboardLayout, text: &str) -> Option<String> {
+        if *self == target {
+            return None;
+        }
+        let source_keys = self.keys().chars().collect::<Vec<_>>();
+        let target_keys = target.keys().chars().collect::<Vec<_>>();
+        debug_assert!(source_keys.len() == target_keys.len(), "Keyboard layouts differ in size");
+
+        let mut changed   = false;
+        let mut converted = String::with_capacity(text.len());
+        for ch in text.chars() {
+            let lower = ch.to_lowercase().next().unwrap_or(ch);
+            let key   = source_keys.iter().position(|&k| k == lower);
+            match key.map(|ix| target_keys[ix]) {
+                Some(target_ch) if target_ch != lower => {
+                    changed = true;
+                    if ch.is_uppercase() {
+                        converted.extend(target_ch.to_uppercase());
+                    } else {
+                        converted.push(target_ch);
+                    }
+                },
+                _ => converted.push(ch),
+            }
+        }
+        if changed { Some(converted) } else { None }
+    }
+}
+
+
+/// Every distinct conversion of the text between given layouts.
+pub fn layout_variants(text: &str, layouts: &[KeyboardLayout]) -> Vec<String> {
+    let mut variants = Vec::new();
+    for &source in layouts {
+        for &target in layouts {
+            if let Some(variant) = source.convert(target, text) {
+                if !variants.contains(&variant) {
+                    variants.push(variant);
+                }
+            }
+        }
+    }
+    variants
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{KeyboardLayout, layout_variants, KEYS_QWERTY, KEYS_QWERTZ, KEYS_AZERTY, KEYS_JCUKEN};
+
+    #[test]
+    fn keyboard_keys_dimensions() {
+        for keys in &[KEYS_QWERTZ, KEYS_AZERTY, KEYS_JCUKEN] {
+            assert_eq!(keys.chars().count(), KEYS_QWERTY.chars().count());
+        }
+    }
+
+    #[test]
+    fn keyboard_key_distances() {
+        let dists = KeyboardLayout::Qwerty.key_distances(1.5);
+        let dist  = |ch1, ch2| dists.iter().find(|&&(c1, c2, _)| c1 == ch1 && c2 == ch2).map(|&(_, _, d)| d);
+        assert_eq!(dist('c', 'v'), Some(1.0));
+        assert_eq!(dist('v', 'c'), Some(1.0));
+        assert_eq!(dist('f', 'r'), Some(1.0625f64.sqrt()));
+        assert_eq!(dist('c', 'g'), None);
+        assert_eq!(dist('q', 'p'), None);
+    }
+
+    #[test]
+    fn keyboard_convert() {
+        use KeyboardLayout::*;
+        assert_eq!(Qwerty.convert(Jcuken, "rjdhbr"),  Some("коврик".to_string()));
+        assert_eq!(Jcuken.convert(Qwerty, "Ьфшдищч"), Some("Mailbox".to_string()));
+        assert_eq!(Qwerty.convert(Qwertz, "zoo 42"),  Some("yoo 42".to_string()));
+        assert_eq!(Azerty.convert(Qwerty, "qwerty"),  Some("azerty".to_string()));
+        assert_eq!(Qwerty.convert(Qwertz, "abc"),     None);
+        assert_eq!(Qwerty.convert(Qwerty, "abc"),     None);
+    }
+
+    #[test]
+    fn keyboard_layout_variants() {
+        use KeyboardLayout::*;
+        assert_eq!(layout_variants("rjdhbr", &[Qwerty, Jcuken]), vec!["коврик".to_string()]);
+        assert_eq!(layout_variants("коврик", &[Qwerty, Jcuken]), vec!["rjdhbr".to_string()]);
+        assert_eq!(layout_variants("zebra",  &[Qwerty, Qwertz, Azerty]), vec![
+            "yebra".to_string(),
+            "webrq".to_string(),
+            "yebrq".to_string(),
+        ]);
+    }
+}
@@ -1,43 +1,99 @@
-use std::cell::RefCell;
 use fnv::{FnvHashMap as HashMap};
 use rust_stemmers::Stemmer;
 use crate::utils::to_vec;
-use super::{CharClass, PartOfSpeech};
+use super::{CharClass, PartOfSpeech, KeyboardLayout, Phonetic, PhoneticKey};
 use super::normalize::Normalize;
 
-const BUFFER_CAPACITY: usize = 20;
+const MAX_KEY_DISTANCE: f64 = 1.5;
 
 
 pub struct Lang {
+    code:         &'static str,
+    keyboard:     KeyboardLayout,
+    key_typos:    bool,
+    key_dists:    HashMap<(char, char), f64>,
     stemmer:      Option<Stemmer>,
+    phonetic:     Option<Phonetic>,
     char_map:     HashMap<char, CharClass>,
     pos_map:      HashMap<Vec<char>, PartOfSpeech>,
     compose_map:  HashMap<Vec<char>, Vec<char>>,
     reduce_map:   HashMap<Vec<char>, Vec<char>>,
-    stem_buffer:  RefCell<String>,
-    norm_buffer1: RefCell<Vec<char>>,
-    norm_buffer2: RefCell<Vec<char>>,
+    translit_map: HashMap<Vec<char>, Vec<char>>,
+    synonym_map:  HashMap<Vec<char>, Vec<String>>,
+    synonym_len:  usize,
 }
 
 
 impl Lang {
     pub fn new() -> Self {
         Self {
+            code:         "",
+            keyboard:     KeyboardLayout::Qwerty,
+            key_typos:    false,
+            key_dists:    HashMap::default(),
             stemmer:      None,
+            phonetic:     None,
             char_map:     HashMap::default(),
             pos_map:      HashMap::default(),
             compose_map:  HashMap::default(),
             reduce_map:   HashMap::default(),
-            stem_buffer:  RefCell::new(String::with_capacity(BUFFER_CAPACITY)),
-            norm_buffer1: RefCell::new(Vec::with_capacity(BUFFER_CAPACITY)),
-            norm_buffer2: RefCell::new(Vec::with_capacity(BUFFER_CAPACITY)),
+            translit_map: HashMap::default(),
+            synonym_map:  HashMap::default(),
+            synonym_len:  0,
         }
     }
 
+    /// Code returned by `detect_lang` for this language, empty if unknown.
+    pub fn set_code(&mut self, code: &'static str) {
+        self.code = code;
+    }
+
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /// Also moves neighbour keys if enabled by `set_key_typos`.
+    pub fn set_keyboard(&mut self, keyboard: KeyboardLayout) {
+        self.keyboard = keyboard;
+        self.set_key_typos(self.key_typos);
+    }
+
+    /// Makes substituting a neighbour key on the keyboard a lesser typo, off by default.
+    pub fn set_key_typos(&mut self, enable: bool) {
+        self.key_typos = enable;
+        self.key_dists = if enable {
+            self.keyboard
+                .key_distances(MAX_KEY_DISTANCE)
+                .into_iter()
+                .map(|(ch1, ch2, dist)| ((ch1, ch2), dist))
+                .collect()
+        } else {
+            HashMap::default()
+        };
+    }
+
+    pub fn keyboard(&self) -> KeyboardLayout {
+        self.keyboard
+    }
+
+    /// Distance between neighbour keys in key widths, `None` for distant keys or with key typos disabled.
+    pub fn key_distance(&self, ch1: char, ch2: char) -> Option<f64> {
+        self.key_dists.get(&(ch1, ch2)).copied()
+    }
+
     pub fn set_stemmer(&mut self, stemmer: Option<Stemmer>) {
         self.stemmer = stemmer;
     }
 
+    /// Words get phonetic keys during tokenization, which must happen after this call.
+    pub fn set_phonetic(&mut self, phonetic: Option<Phonetic>) {
+        self.phonetic = phonetic;
+    }
+
+    pub fn phonetic(&self) -> Option<Phonetic> {
+        self.phonetic
+    }
+
     pub fn add_char_class(&mut self, ch: char, class: CharClass) {
         self.char_map.insert(ch, class);
     }
@@ -62,18 +118,91 @@ impl Lang {
         self.reduce_map.insert(to_vec(from), to_vec(to));
     }
 
-    pub fn stem(&self, word: &[char]) -> usize {
+    /// Replaces lowercase `from` with `to` after stemming, so texts in different scripts match each other.
+    /// Unlike reductions `to` can't be shorter than `from`. Synonyms added before are looked up transliterated too.
+    pub fn add_transliteration(&mut self, from: &str, to: &str) {
+        let from = to_vec(from);
+        let to   = to_vec(to);
+        if to.len() < from.len() || from.is_empty() { return; }
+        self.translit_map.insert(from, to);
+
+        let synonym_map = std::mem::take(&mut self.synonym_map);
+        for (key, alternatives) in synonym_map {
+            let key    = self.transliterate(&key).map(|(_, chars)| chars).unwrap_or(key);
+            let merged = self.synonym_map.entry(key).or_default();
+            for alternative in alternatives {
+                if !merged.contains(&alternative) {
+                    merged.push(alternative);
+                }
+            }
+        }
+    }
+
+    /// Lets a query word or phrase `from` match records containing `to`, but not vice versa.
+    pub fn add_synonym(&mut self, from: &str, to: &str) {
+        let key = self.synonym_key(from);
+        if key.is_empty() { return; }
+        let len = key.split(|&ch| ch == ' ').count();
+        let alternatives = self.synonym_map.entry(key).or_default();
+        if !alternatives.iter().any(|alt| alt == to) {
+            alternatives.push(to.to_string());
+        }
+        self.synonym_len = max!(self.synonym_len, len);
+    }
+
+    /// Makes every word or phrase of the group match the others.
+    pub fn add_synonyms(&mut self, group: &[&str]) {
+        for from in group {
+            for to in group {
+                if from != to {
+                    self.add_synonym(from, to);
+                }
+            }
+        }
+    }
+
+    /// Alternatives for query words, `words` are lowercased chars joined with spaces.
+    pub fn get_synonyms(&self, words: &[char]) -> &[String] {
+        self.synonym_map
+            .get(words)
+            .map(|alts| &alts[..])
+            .unwrap_or(&[])
+    }
+
+    /// Max number of words in a phrase having synonyms.
+    pub fn synonym_len(&self) -> usize {
+        self.synonym_len
+    }
+
+    /// Normalized like query chars, transliteration included.
+    fn synonym_key(&self, phrase: &str) -> Vec<char> {
+        let source   = to_vec(phrase);
+        let composed = self.unicode_compose(&source).unwrap_or(source);
+        let reduced  = self.unicode_reduce(&composed).map(|(_, chars)| chars).unwrap_or(composed);
+        let lowered  = reduced
+            .iter()
+            .map(|ch| ch.to_lowercase().next().unwrap_or(*ch))
+            .collect::<String>();
+        let key = to_vec(lowered.split_whitespace().collect::<Vec<_>>().join(" "));
+        self.transliterate(&key).map(|(_, chars)| chars).unwrap_or(key)
+    }
+
+    /// `buffer` is scratch space, reused for every word of a text.
+    pub fn stem(&self, word: &[char], buffer: &mut String) -> usize {
         if let Some(stemmer) = &self.stemmer {
-            let buffer = &mut *self.stem_buffer.borrow_mut();
             buffer.clear();
             buffer.extend(word.iter());
-            let stem = stemmer.stem(&buffer);
+            let stem = stemmer.stem(buffer);
             stem.chars().count()
         } else {
             word.len()
         }
     }
 
+    pub fn phonetic_key(&self, word: &[char]) -> Option<PhoneticKey> {
+        self.phonetic?.encode(word)
+    }
+
     pub fn get_pos(&self, word: &[char]) -> Option<PartOfSpeech> {
         self.pos_map.get(word).cloned()
     }
@@ -83,38 +212,54 @@ impl Lang {
     }
 
     pub fn unicode_compose(&self, word: &[char]) -> Option<Vec<char>> {
-        let buffer = &mut *self.norm_buffer1.borrow_mut();
-        buffer.clear();
-
+        let mut output = Vec::with_capacity(word.len());
         for (_, norm_chunk) in Normalize::new(word, &self.compose_map) {
-            buffer.extend(norm_chunk);
+            output.extend(norm_chunk);
         }
 
-        if &buffer[..] == word {
+        if &output[..] == word {
             None
         } else {
-            Some(buffer.clone())
+            Some(output)
         }
     }
 
-    pub fn unicode_reduce(&self, word: &[char]) -> Option<(Vec<char>, Vec<char>)> {
-        let buffer1 = &mut *self.norm_buffer1.borrow_mut();
-        let buffer2 = &mut *self.norm_buffer2.borrow_mut();
-        buffer1.clear();
-        buffer2.clear();
+    /// Positions of source chars within the transliterated ones, followed by the total length,
+    /// and transliterated chars. Returns `None` if nothing changes.
+    pub fn transliterate(&self, chars: &[char]) -> Option<(Vec<usize>, Vec<char>)> {
+        if self.translit_map.is_empty() {
+            return None;
+        }
+        let mut positions = Vec::with_capacity(chars.len() + 1);
+        let mut output    = Vec::with_capacity(chars.len());
+        for (chunk, translit) in Normalize::new(chars, &self.translit_map) {
+            for i in 0 .. chunk.len() {
+                positions.push(output.len() + i);
+            }
+            output.extend(translit);
+        }
+        positions.push(output.len());
 
+        if &output[..] == chars {
+            None
+        } else {
+            Some((positions, output))
+        }
+    }
+
+    pub fn unicode_reduce(&self, word: &[char]) -> Option<(Vec<char>, Vec<char>)> {
+        let mut padded  = Vec::with_capacity(word.len());
+        let mut reduced = Vec::with_capacity(word.len());
         for (word_chunk, norm_chunk) in Normalize::new(word, &self.reduce_map) {
-            buffer1.extend(word_chunk);
-            buffer2.extend(norm_chunk);
-            for _ in 0 .. norm_chunk.len() - word_chunk.len() {
-                buffer1.push('\0');
-            }
+            padded.extend(word_chunk);
+            reduced.extend(norm_chunk);
+            padded.resize(padded.len() + norm_chunk.len() - word_chunk.len(), '\0');
         }
 
-        if &buffer2[..] == word {
+        if &reduced[..] == word {
             None
         } else {
-            Some((buffer1.clone(), buffer2.clone()))
+            Some((padded, reduced))
         }
     }
 }
@@ -194,6 +339,24 @@ mod tests {
         assert_eq!(pos, None);
     }
 
+    #[test]
+    fn get_synonyms_basic() {
+        let mut lang = get_lang();
+        lang.add_synonym("TV", "television");
+        lang.add_synonyms(&["hoodie", "hooded  sweatshirt"]);
+        assert_eq!(lang.get_synonyms(&to_vec("tv")), &["television".to_string()]);
+        assert_eq!(lang.get_synonyms(&to_vec("television")), &[] as &[String]);
+        assert_eq!(lang.get_synonyms(&to_vec("hooded sweatshirt")), &["hoodie".to_string()]);
+        assert_eq!(lang.synonym_len(), 2);
+    }
+
+    #[test]
+    fn get_synonyms_reduced() {
+        let mut lang = get_lang();
+        lang.add_synonym("fóo", "bar");
+        assert_eq!(lang.get_synonyms(&to_vec("foo")), &["bar".to_string()]);
+    }
+
     #[test]
     fn get_char_class_known() {
         let class_x = get_lang().get_char_class('x');
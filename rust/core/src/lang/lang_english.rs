@@ -13,7 +13,7 @@ use PartOfSpeech::{
 };
 
 
-const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
+pub(super) const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
     (Article, "a"),
     (Article, "an"),
     (Article, "the"),
@@ -136,6 +136,8 @@ const UTF_REDUCE_MAP: &[(&'static str, &'static str)] = &[];
 pub fn lang_english() -> Lang {
     let mut lang = Lang::new();
 
+    lang.set_code("en");
+
     lang.set_stemmer(Some(Stemmer::create(Algorithm::English)));
 
     for (from, to) in UTF_COMPOSE_MAP { lang.add_unicode_composition(from, to); }
@@ -160,7 +162,7 @@ mod tests {
     fn stem() {
         let lang = lang_english();
         let w    = to_vec("universe");
-        assert_eq!(lang.stem(&w), 7);
+        assert_eq!(lang.stem(&w, &mut String::new()), 7);
     }
 
     #[test]
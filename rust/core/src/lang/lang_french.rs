@@ -2,7 +2,7 @@
 
 use rust_stemmers::{Algorithm, Stemmer};
 use super::{CharClass, PartOfSpeech};
-use super::Lang;
+use super::{Lang, KeyboardLayout};
 use super::constants::CHAR_CLASSES_LATIN;
 
 use PartOfSpeech::{
@@ -17,7 +17,7 @@ use CharClass::{
     Vowel,
 };
 
-const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
+pub(super) const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
     (Article, "le"),
     (Article, "la"),
     (Article, "les"),
@@ -305,6 +305,9 @@ const UTF_REDUCE_MAP: &[(&'static str, &'static str)] = &[
 pub fn lang_french() -> Lang {
     let mut lang = Lang::new();
 
+    lang.set_code("fr");
+    lang.set_keyboard(KeyboardLayout::Azerty);
+
     lang.set_stemmer(Some(Stemmer::create(Algorithm::French)));
 
     for (from, to)   in UTF_COMPOSE_MAP    { lang.add_unicode_composition(from, to); }
@@ -329,7 +332,7 @@ mod tests {
     pub fn stem() {
         let lang = lang_french();
         let w    = to_vec("université");
-        assert_eq!(lang.stem(&w), 7);
+        assert_eq!(lang.stem(&w, &mut String::new()), 7);
     }
 
     #[test]
@@ -2,7 +2,7 @@
 
 use rust_stemmers::{Algorithm, Stemmer};
 use super::{CharClass, PartOfSpeech};
-use super::Lang;
+use super::{Lang, KeyboardLayout};
 use super::constants::CHAR_CLASSES_LATIN;
 
 use CharClass::{
@@ -17,7 +17,7 @@ use PartOfSpeech::{
 };
 
 
-const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
+pub(super) const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
     (Article, "das"),
     (Article, "dem"),
     (Article, "den"),
@@ -137,6 +137,9 @@ const UTF_REDUCE_MAP: &[(&'static str, &'static str)] = &[
 pub fn lang_german() -> Lang {
     let mut lang = Lang::new();
 
+    lang.set_code("de");
+    lang.set_keyboard(KeyboardLayout::Qwertz);
+
     lang.set_stemmer(Some(Stemmer::create(Algorithm::German)));
 
     for (from, to) in UTF_COMPOSE_MAP { lang.add_unicode_composition(from, to); }
@@ -161,7 +164,7 @@ mod tests {
     pub fn stem() {
         let lang = lang_german();
         let w    = to_vec("singen");
-        assert_eq!(lang.stem(&w), 4);
+        assert_eq!(lang.stem(&w, &mut String::new()), 4);
     }
 
     #[test]
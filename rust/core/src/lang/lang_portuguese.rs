@@ -12,7 +12,7 @@ use PartOfSpeech::{
 };
 
 
-const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
+pub(super) const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
     (Article, "o"),
     (Article, "a"),
     (Article, "os"),
@@ -208,6 +208,8 @@ const UTF_REDUCE_MAP: &[(&'static str, &'static str)] = &[
 pub fn lang_portuguese() -> Lang {
     let mut lang = Lang::new();
 
+    lang.set_code("pt");
+
     lang.set_stemmer(Some(Stemmer::create(Algorithm::Portuguese)));
 
     for (from, to) in UTF_COMPOSE_MAP { lang.add_unicode_composition(from, to); }
@@ -232,7 +234,7 @@ mod tests {
     pub fn stem() {
         let lang = lang_portuguese();
         let w    = to_vec("quilométricas");
-        assert_eq!(lang.stem(&w), 9);
+        assert_eq!(lang.stem(&w, &mut String::new()), 9);
     }
 
     #[test]
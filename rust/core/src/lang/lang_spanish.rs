@@ -12,7 +12,7 @@ use PartOfSpeech::{
 };
 
 
-const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
+pub(super) const FUNCTION_WORDS: &[(PartOfSpeech, &'static str)] = &[
     (Article, "el"),
     (Article, "la"),
     (Article, "los"),
@@ -161,6 +161,8 @@ const UTF_REDUCE_MAP: &[(&'static str, &'static str)] = &[
 pub fn lang_spanish() -> Lang {
     let mut lang = Lang::new();
 
+    lang.set_code("es");
+
     lang.set_stemmer(Some(Stemmer::create(Algorithm::Spanish)));
 
     for (from, to) in UTF_COMPOSE_MAP { lang.add_unicode_composition(from, to); }
@@ -185,7 +187,7 @@ mod tests {
     pub fn stem() {
         let lang = lang_spanish();
         let w = to_vec("torniquete");
-        assert_eq!(lang.stem(&w), 9);
+        assert_eq!(lang.stem(&w, &mut String::new()), 9);
     }
 
     #[test]
@@ -0,0 +1,269 @@
+use std::fmt;
+use std::error::Error;
+use rust_stemmers::{Algorithm, Stemmer};
+use super::{CharClass, PartOfSpeech, Phonetic};
+use super::Lang;
+
+
+const STEMMERS: &[(&str, Algorithm)] = &[
+    ("arabic",     Algorithm::Arabic),
+    ("danish",     Algorithm::Danish),
+    ("dutch",      Algorithm::Dutch),
+    ("english",    Algorithm::English),
+    ("finnish",    Algorithm::Finnish),
+    ("french",     Algorithm::French),
+    ("german",     Algorithm::German),
+    ("greek",      Algorithm::Greek),
+    ("hungarian",  Algorithm::Hungarian),
+    ("italian",    Algorithm::Italian),
+    ("norwegian",  Algorithm::Norwegian),
+    ("portuguese", Algorithm::Portuguese),
+    ("romanian",   Algorithm::Romanian),
+    ("russian",    Algorithm::Russian),
+    ("spanish",    Algorithm::Spanish),
+    ("swedish",    Algorithm::Swedish),
+    ("tamil",      Algorithm::Tamil),
+    ("turkish",    Algorithm::Turkish),
+];
+
+
+/// Language data that can be stored outside of the code and turned into `Lang` at runtime.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct LangSpec {
+    pub stemmer:        Option<Algorithm>,
+    pub phonetic:       Option<Phonetic>,
+    pub function_words: Vec<(PartOfSpeech, String)>,
+    pub char_classes:   Vec<(CharClass, char)>,
+    pub compositions:   Vec<(String, String)>,
+    pub reductions:     Vec<(String, String)>,
+    pub translits:      Vec<(String, String)>,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LangSpecError {
+    UnknownDirective(usize, String),
+    UnknownStemmer(usize, String),
+    UnknownPhonetic(usize, String),
+    UnknownPartOfSpeech(usize, String),
+    InvalidChar(usize, String),
+    MissingValue(usize),
+}
+
+
+impl fmt::Display for LangSpecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LangSpecError::UnknownDirective(line, name)    => write!(f, "Unknown directive {:?} at line {} of lang spec", name, line),
+            LangSpecError::UnknownStemmer(line, name)      => write!(f, "Unknown stemmer {:?} at line {} of lang spec", name, line),
+            LangSpecError::UnknownPhonetic(line, name)     => write!(f, "Unknown phonetic algorithm {:?} at line {} of lang spec", name, line),
+            LangSpecError::UnknownPartOfSpeech(line, name) => write!(f, "Unknown part of speech {:?} at line {} of lang spec", name, line),
+            LangSpecError::InvalidChar(line, value)        => write!(f, "Expected a single char, got {:?} at line {} of lang spec", value, line),
+            LangSpecError::MissingValue(line)              => write!(f, "Missing value at line {} of lang spec", line),
+        }
+    }
+}
+
+
+impl Error for LangSpecError { }
+
+
+impl LangSpec {
+    /// Parses a line based format, lines start with a directive followed by space separated values:
+    ///
+    /// ```text
+    /// # comment
+    /// stemmer english
+    /// phonetic double_metaphone
+    /// article a an the
+    /// vowel a e i o u y
+    /// consonant b c d f g
+    /// compose ó ó
+    /// reduce ó o ß ss
+    /// translit щ shch
+    /// ```
+    ///
+    /// Any part of speech works as a directive listing function words, any char class like `punctuation` listing chars,
+    /// `compose`, `reduce` and `translit` take pairs of values. Line numbers in errors start with 1.
+    pub fn parse(source: &str) -> Result<Self, LangSpecError> {
+        let mut spec = LangSpec::default();
+        for (ix, line) in source.lines().enumerate() {
+            let num        = ix + 1;
+            let mut values = line.split_whitespace();
+            let directive  = match values.next() {
+                Some(directive) if !directive.starts_with('#') => directive,
+                _ => continue,
+            };
+            match directive {
+                "stemmer" => {
+                    let name = values.next().ok_or(LangSpecError::MissingValue(num))?;
+                    let algorithm = STEMMERS
+                        .iter()
+                        .find(|(n, _)| *n == name)
+                        .map(|&(_, algorithm)| algorithm)
+                        .ok_or_else(|| LangSpecError::UnknownStemmer(num, name.to_string()))?;
+                    spec.stemmer = Some(algorithm);
+                },
+                "phonetic" => {
+                    let name = values.next().ok_or(LangSpecError::MissingValue(num))?;
+                    let phonetic = Phonetic::from_name(name)
+                        .ok_or_else(|| LangSpecError::UnknownPhonetic(num, name.to_string()))?;
+                    spec.phonetic = Some(phonetic);
+                },
+                "compose" | "reduce" | "translit" => {
+                    let values = values.collect::<Vec<_>>();
+                    if values.is_empty() || values.len() % 2 != 0 {
+                        return Err(LangSpecError::MissingValue(num));
+                    }
+                    let target = match directive {
+                        "compose" => &mut spec.compositions,
+                        "reduce"  => &mut spec.reductions,
+                        _         => &mut spec.translits,
+                    };
+                    for pair in values.chunks(2) {
+                        target.push((pair[0].to_string(), pair[1].to_string()));
+                    }
+                },
+                _ => {
+                    if let Some(class) = CharClass::from_name(directive) {
+                        for value in values {
+                            let mut chars = value.chars();
+                            match (chars.next(), chars.next()) {
+                                (Some(ch), None) => spec.char_classes.push((class, ch)),
+                                _ => return Err(LangSpecError::InvalidChar(num, value.to_string())),
+                            }
+                        }
+                        continue;
+                    }
+                    let pos = PartOfSpeech::from_name(directive)
+                        .ok_or_else(|| LangSpecError::UnknownDirective(num, directive.to_string()))?;
+                    for word in values {
+                        spec.function_words.push((pos, word.to_string()));
+                    }
+                },
+            }
+        }
+        Ok(spec)
+    }
+
+    pub fn to_lang(&self) -> Lang {
+        let mut lang = Lang::new();
+
+        lang.set_stemmer(self.stemmer.map(Stemmer::create));
+        lang.set_phonetic(self.phonetic);
+
+        for (from, to) in &self.compositions { lang.add_unicode_composition(from, to); }
+        for (from, to) in &self.reductions   { lang.add_unicode_reduction(from, to); }
+        for (from, to) in &self.translits    { lang.add_transliteration(from, to); }
+
+        for (pos, word) in &self.function_words { lang.add_pos(word, *pos); }
+
+        for &(class, ch) in &self.char_classes { lang.add_char_class(ch, class); }
+
+        lang
+    }
+}
+
+
+/// Writes the format read by `LangSpec::parse`.
+impl fmt::Display for LangSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(algorithm) = self.stemmer {
+            let (name, _) = STEMMERS.iter().find(|(_, a)| *a == algorithm).ok_or(fmt::Error)?;
+            writeln!(f, "stemmer {}", name)?;
+        }
+        if let Some(phonetic) = self.phonetic {
+            writeln!(f, "phonetic {}", phonetic.name())?;
+        }
+        for (pos, word) in &self.function_words {
+            writeln!(f, "{} {}", pos.name(), word)?;
+        }
+        for (class, ch) in &self.char_classes {
+            writeln!(f, "{} {}", class.name(), ch)?;
+        }
+        for (from, to) in &self.compositions {
+            writeln!(f, "compose {} {}", from, to)?;
+        }
+        for (from, to) in &self.reductions {
+            writeln!(f, "reduce {} {}", from, to)?;
+        }
+        for (from, to) in &self.translits {
+            writeln!(f, "translit {} {}", from, to)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rust_stemmers::Algorithm;
+    use crate::utils::to_vec;
+    use super::super::{CharClass, PartOfSpeech, Phonetic};
+    use super::{LangSpec, LangSpecError};
+
+    const SOURCE: &str = "
+        # Made up language
+        stemmer english
+        phonetic cologne
+        article a the
+        particle not
+        vowel a e
+        consonant b
+        compose ó ó
+        reduce ó o ß ss
+        translit щ shch
+    ";
+
+    #[test]
+    fn lang_spec_parse() {
+        let spec = LangSpec::parse(SOURCE).unwrap();
+        assert_eq!(spec.stemmer, Some(Algorithm::English));
+        assert_eq!(spec.phonetic, Some(Phonetic::Cologne));
+        assert_eq!(spec.function_words, vec![
+            (PartOfSpeech::Article,  "a".to_string()),
+            (PartOfSpeech::Article,  "the".to_string()),
+            (PartOfSpeech::Particle, "not".to_string()),
+        ]);
+        assert_eq!(spec.char_classes, vec![
+            (CharClass::Vowel,     'a'),
+            (CharClass::Vowel,     'e'),
+            (CharClass::Consonant, 'b'),
+        ]);
+        assert_eq!(spec.compositions, vec![("ó".to_string(), "ó".to_string())]);
+        assert_eq!(spec.reductions, vec![
+            ("ó".to_string(), "o".to_string()),
+            ("ß".to_string(), "ss".to_string()),
+        ]);
+        assert_eq!(spec.translits, vec![("щ".to_string(), "shch".to_string())]);
+    }
+
+    #[test]
+    fn lang_spec_parse_errors() {
+        assert_eq!(LangSpec::parse("stemmer"),            Err(LangSpecError::MissingValue(1)));
+        assert_eq!(LangSpec::parse("stemmer klingon"),    Err(LangSpecError::UnknownStemmer(1, "klingon".to_string())));
+        assert_eq!(LangSpec::parse("phonetic soundex"),   Err(LangSpecError::UnknownPhonetic(1, "soundex".to_string())));
+        assert_eq!(LangSpec::parse("\nnoun x\nverbs y"),  Err(LangSpecError::UnknownDirective(3, "verbs".to_string())));
+        assert_eq!(LangSpec::parse("vowel ae"),           Err(LangSpecError::InvalidChar(1, "ae".to_string())));
+        assert_eq!(LangSpec::parse("reduce ó"),           Err(LangSpecError::MissingValue(1)));
+    }
+
+    #[test]
+    fn lang_spec_roundtrip() {
+        let spec = LangSpec::parse(SOURCE).unwrap();
+        assert_eq!(LangSpec::parse(&spec.to_string()), Ok(spec));
+        let spec = LangSpec::parse("punctuation ¡\nnot_alpha_num ·").unwrap();
+        assert_eq!(spec.to_string(), "punctuation ¡\nnot_alpha_num ·\n");
+    }
+
+    #[test]
+    fn lang_spec_to_lang() {
+        let lang = LangSpec::parse(SOURCE).unwrap().to_lang();
+        assert_eq!(lang.stem(&to_vec("universe"), &mut String::new()), 7);
+        assert_eq!(lang.get_pos(&to_vec("the")), Some(PartOfSpeech::Article));
+        assert_eq!(lang.get_char_class('b'), Some(CharClass::Consonant));
+        assert_eq!(lang.get_char_class('c'), None);
+        assert_eq!(lang.unicode_reduce(&to_vec("ß")), Some((to_vec("ß\0"), to_vec("ss"))));
+        assert_eq!(lang.transliterate(&to_vec("щ")), Some((vec![0, 4], to_vec("shch"))));
+    }
+}
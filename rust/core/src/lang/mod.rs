@@ -3,6 +3,7 @@ mod char_class;
 mod normalize;
 mod pos;
 mod lang;
+mod lang_spec;
 mod lang_basic;
 mod lang_english;
 mod lang_french;
@@ -10,10 +11,15 @@ mod lang_german;
 mod lang_portuguese;
 mod lang_russian;
 mod lang_spanish;
+mod detect;
+mod keyboard;
+mod phonetic;
 
+pub use constants::CYRILLIC_TO_LATIN;
 pub use char_class::{CharClass, CharPattern};
 pub use pos::PartOfSpeech;
 pub use lang::Lang;
+pub use lang_spec::{LangSpec, LangSpecError};
 pub use lang_basic::lang_basic;
 pub use lang_german::lang_german;
 pub use lang_english::lang_english;
@@ -21,3 +27,6 @@ pub use lang_french::lang_french;
 pub use lang_spanish::lang_spanish;
 pub use lang_portuguese::lang_portuguese;
 pub use lang_russian::lang_russian;
+pub use detect::detect_lang;
+pub use keyboard::{KeyboardLayout, layout_variants};
+pub use phonetic::{Phonetic, PhoneticKey};
@@ -0,0 +1,339 @@
+use std::fmt;
+
+
+const KEY_LEN: usize = 6;
+
+
+/// Algorithm turning a word into a code of how it sounds, so different spellings of a name match.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Phonetic {
+    /// Primary code of Double Metaphone, for English and other Latin script languages.
+    /// Alternate codes are not produced, the code is cut to 4 chars.
+    DoubleMetaphone,
+    /// Kölner Phonetik, for German.
+    Cologne,
+}
+
+
+/// Phonetic code of a word, up to 6 ASCII chars.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PhoneticKey([u8; KEY_LEN]);
+
+
+impl PhoneticKey {
+    fn new(code: &str) -> Option<Self> {
+        if code.is_empty() {
+            return None;
+        }
+        let mut bytes = [0; KEY_LEN];
+        for (byte, ch) in bytes.iter_mut().zip(code.bytes()) {
+            *byte = ch;
+        }
+        Some(Self(bytes))
+    }
+
+    pub fn to_bytes(&self) -> [u8; KEY_LEN] {
+        self.0
+    }
+
+    /// All zeros is `None`, as no key is empty.
+    pub fn from_bytes(bytes: [u8; KEY_LEN]) -> Option<Self> {
+        if bytes[0] == 0 { None } else { Some(Self(bytes)) }
+    }
+
+    /// Packs the key into chars from a private use plane, so it can sit in the trigram index.
+    pub fn to_gram(&self) -> [char; 3] {
+        let mut gram = ['\0'; 3];
+        for (ch, pair) in gram.iter_mut().zip(self.0.chunks(2)) {
+            let code = 0x100000 + ((pair[0] as u32) << 8) + pair[1] as u32;
+            *ch = std::char::from_u32(code).unwrap_or('\0');
+        }
+        gram
+    }
+}
+
+
+impl fmt::Debug for PhoneticKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let code = self.0.iter()
+            .take_while(|&&byte| byte != 0)
+            .map(|&byte| byte as char)
+            .collect::<String>();
+        write!(f, "PhoneticKey({:?})", code)
+    }
+}
+
+
+impl Phonetic {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Phonetic::DoubleMetaphone => "double_metaphone",
+            Phonetic::Cologne         => "cologne",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "double_metaphone" => Some(Phonetic::DoubleMetaphone),
+            "cologne"          => Some(Phonetic::Cologne),
+            _ => None,
+        }
+    }
+
+    /// Cologne phonetics for German, Double Metaphone otherwise.
+    pub fn for_lang(code: &str) -> Self {
+        if code == "de" { Phonetic::Cologne } else { Phonetic::DoubleMetaphone }
+    }
+
+    /// Takes a lowercase word, returns `None` if nothing in it is encoded.
+    pub fn encode(&self, word: &[char]) -> Option<PhoneticKey> {
+        let chars = word.iter().filter_map(|&ch| fold_latin(ch)).collect::<Vec<_>>();
+        let code  = match self {
+            Phonetic::DoubleMetaphone => double_metaphone(&chars),
+            Phonetic::Cologne         => cologne(&chars),
+        };
+        PhoneticKey::new(&code)
+    }
+}
+
+
+/// Lowercase ASCII letter closest to the char, `ß` becomes `s`.
+fn fold_latin(ch: char) -> Option<char> {
+    let folded = match ch {
+        'a' ..= 'z' => ch,
+        'à' | 'á' | 'â' | 'ã' | 'ä' | 'å' | 'æ' => 'a',
+        'è' | 'é' | 'ê' | 'ë' => 'e',
+        'ì' | 'í' | 'î' | 'ï' => 'i',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ö' | 'ø' | 'œ' => 'o',
+        'ù' | 'ú' | 'û' | 'ü' => 'u',
+        'ý' | 'ÿ' => 'y',
+        'ç' | 'ß' => 's',
+        'ñ' => 'n',
+        _ => return None,
+    };
+    Some(folded)
+}
+
+
+fn is_vowel(ch: char) -> bool {
+    matches!(ch, 'a' | 'e' | 'i' | 'o' | 'u' | 'y')
+}
+
+
+fn double_metaphone(word: &[char]) -> String {
+    let at = |i: usize| word.get(i).copied().unwrap_or('\0');
+    let mut code = String::new();
+    let mut i    = 0;
+
+    match (at(0), at(1)) {
+        ('g', 'n') | ('k', 'n') | ('p', 'n') | ('w', 'r') | ('p', 's') => i = 1,
+        ('x', _) => {
+            code.push('S');
+            i = 1;
+        },
+        ('w', 'h') => {
+            code.push('A');
+            i = 2;
+        },
+        ('w', next) if is_vowel(next) => {
+            code.push('A');
+            i = 1;
+        },
+        _ => {},
+    }
+
+    while i < word.len() && code.len() < 4 {
+        let ch   = at(i);
+        let next = at(i + 1);
+        let mut step = if next == ch { 2 } else { 1 };
+        match ch {
+            _ if is_vowel(ch) => {
+                if i == 0 { code.push('A'); }
+                step = 1;
+            },
+            'b' => code.push('P'),
+            'c' => match next {
+                'h' => {
+                    code.push(if at(i + 2) == 'r' { 'K' } else { 'X' });
+                    step = 2;
+                },
+                'i' if at(i + 2) == 'a' => {
+                    code.push('X');
+                    step = 3;
+                },
+                'e' | 'i' | 'y' => code.push('S'),
+                'k' | 'q' => {
+                    code.push('K');
+                    step = 2;
+                },
+                _ => code.push('K'),
+            },
+            'd' => match (next, at(i + 2)) {
+                ('g', 'e') | ('g', 'i') | ('g', 'y') => {
+                    code.push('J');
+                    step = 3;
+                },
+                ('t', _) => {
+                    code.push('T');
+                    step = 2;
+                },
+                _ => code.push('T'),
+            },
+            'g' => match next {
+                'h' => {
+                    if i == 0 || !is_vowel(at(i - 1)) { code.push('K'); }
+                    step = 2;
+                },
+                'n' => {},
+                'e' | 'i' | 'y' => code.push('J'),
+                _ => code.push('K'),
+            },
+            'h' if (i == 0 || is_vowel(at(i - 1))) && is_vowel(next) => code.push('H'),
+            'p' if next == 'h' => {
+                code.push('F');
+                step = 2;
+            },
+            'p' => code.push('P'),
+            's' => match (next, at(i + 2)) {
+                ('h', _) => {
+                    code.push('X');
+                    step = 2;
+                },
+                ('i', 'o') | ('i', 'a') => {
+                    code.push('X');
+                    step = 3;
+                },
+                ('c', 'h') => {
+                    code.push_str("SK");
+                    step = 3;
+                },
+                ('c', 'e') | ('c', 'i') | ('c', 'y') => {
+                    code.push('S');
+                    step = 2;
+                },
+                ('c', _) => {
+                    code.push_str("SK");
+                    step = 2;
+                },
+                _ => code.push('S'),
+            },
+            't' => match (next, at(i + 2)) {
+                ('h', _) => {
+                    code.push('0');
+                    step = 2;
+                },
+                ('i', 'o') | ('i', 'a') => {
+                    code.push('X');
+                    step = 3;
+                },
+                ('c', 'h') => {},
+                _ => code.push('T'),
+            },
+            'f' | 'v' => code.push('F'),
+            'j' => code.push('J'),
+            'k' | 'q' => code.push('K'),
+            'l' => code.push('L'),
+            'm' => code.push('M'),
+            'n' => code.push('N'),
+            'r' => code.push('R'),
+            'x' => code.push_str("KS"),
+            'z' => code.push('S'),
+            _ => {},
+        }
+        i += step;
+    }
+
+    code.truncate(4);
+    code
+}
+
+
+fn cologne(word: &[char]) -> String {
+    let at = |i: usize| word.get(i).copied().unwrap_or('\0');
+    let mut digits = Vec::with_capacity(word.len());
+
+    for (i, &ch) in word.iter().enumerate() {
+        let prev = if i == 0 { '\0' } else { at(i - 1) };
+        let next = at(i + 1);
+        let digit = match ch {
+            'a' | 'e' | 'i' | 'j' | 'o' | 'u' | 'y' => '0',
+            'h' => continue,
+            'b' => '1',
+            'p' => if next == 'h' { '3' } else { '1' },
+            'd' | 't' => if matches!(next, 'c' | 's' | 'z') { '8' } else { '2' },
+            'f' | 'v' | 'w' => '3',
+            'g' | 'k' | 'q' => '4',
+            'c' if i == 0 => if matches!(next, 'a' | 'h' | 'k' | 'l' | 'o' | 'q' | 'r' | 'u' | 'x') { '4' } else { '8' },
+            'c' => if matches!(next, 'a' | 'h' | 'k' | 'o' | 'q' | 'u' | 'x') && !matches!(prev, 's' | 'z') { '4' } else { '8' },
+            'x' => {
+                if !matches!(prev, 'c' | 'k' | 'q') { digits.push('4'); }
+                '8'
+            },
+            'l' => '5',
+            'm' | 'n' => '6',
+            'r' => '7',
+            's' | 'z' => '8',
+            _ => continue,
+        };
+        digits.push(digit);
+    }
+    digits.dedup();
+
+    digits
+        .iter()
+        .enumerate()
+        .filter(|&(i, &digit)| i == 0 || digit != '0')
+        .map(|(_, &digit)| digit)
+        .take(KEY_LEN)
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::utils::to_vec;
+    use super::{Phonetic, PhoneticKey};
+
+    fn encode(phonetic: Phonetic, word: &str) -> Option<PhoneticKey> {
+        phonetic.encode(&to_vec(word))
+    }
+
+    #[test]
+    fn phonetic_double_metaphone() {
+        let check = |word1: &str, word2: &str, code: &str| {
+            assert_eq!(encode(Phonetic::DoubleMetaphone, word1), PhoneticKey::new(code), "{}", word1);
+            assert_eq!(encode(Phonetic::DoubleMetaphone, word2), PhoneticKey::new(code), "{}", word2);
+        };
+        check("john",      "jon",     "JN");
+        check("smith",     "smyth",   "SM0");
+        check("catherine", "kathryn", "K0RN");
+        check("stephen",   "steven",  "STFN");
+        check("philip",    "phillip", "FLP");
+        check("knight",    "nite",    "NT");
+        check("ashley",    "ashlee",  "AXL");
+        assert_eq!(encode(Phonetic::DoubleMetaphone, "42"), None);
+    }
+
+    #[test]
+    fn phonetic_cologne() {
+        let check = |word: &str, code: &str| {
+            assert_eq!(encode(Phonetic::Cologne, word), PhoneticKey::new(code), "{}", word);
+        };
+        check("müller",       "657");
+        check("lüdenscheidt", "52682");
+        check("meyer",        "67");
+        check("maier",        "67");
+        check("schmidt",      "862");
+        check("schmitt",      "862");
+        check("axel",         "0485");
+    }
+
+    #[test]
+    fn phonetic_key_bytes() {
+        let key = PhoneticKey::new("SM0").unwrap();
+        assert_eq!(PhoneticKey::from_bytes(key.to_bytes()), Some(key));
+        assert_eq!(PhoneticKey::from_bytes([0; 6]), None);
+        assert_ne!(key.to_gram(), PhoneticKey::new("SM").unwrap().to_gram());
+        assert_eq!(format!("{:?}", key), "PhoneticKey(\"SM0\")");
+    }
+}
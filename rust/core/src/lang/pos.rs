@@ -11,3 +11,28 @@ pub enum PartOfSpeech {
     Intejection,
     Article,
 }
+
+
+const NAMES: &[(PartOfSpeech, &str)] = &[
+    (PartOfSpeech::Noun,        "noun"),
+    (PartOfSpeech::Pronoun,     "pronoun"),
+    (PartOfSpeech::Verb,        "verb"),
+    (PartOfSpeech::Adjective,   "adjective"),
+    (PartOfSpeech::Adverb,      "adverb"),
+    (PartOfSpeech::Preposition, "preposition"),
+    (PartOfSpeech::Conjunction, "conjunction"),
+    (PartOfSpeech::Particle,    "particle"),
+    (PartOfSpeech::Intejection, "interjection"),
+    (PartOfSpeech::Article,     "article"),
+];
+
+
+impl PartOfSpeech {
+    pub fn name(&self) -> &'static str {
+        NAMES.iter().find(|(pos, _)| pos == self).map(|&(_, name)| name).unwrap_or("")
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        NAMES.iter().find(|&&(_, n)| n == name).map(|&(pos, _)| pos)
+    }
+}
@@ -5,15 +5,20 @@ pub mod tokenization;
 
 mod store;
 mod search;
+mod error;
 pub mod lang;
 
 use std::cell::RefCell;
+use std::sync::PoisonError;
 use fnv::{FnvHashMap as HashMap};
 
 pub use tokenization::{Word, WordShape, WordView, Text, TextOwn, TextRef, tokenize_query};
-pub use store::{Record, Store, DEFAULT_LIMIT};
-pub use search::{SearchResult};
-pub use lang::Lang;
+pub use store::{Record, Store, Field, Attribute, DuplicatePolicy, SnapshotError, DEFAULT_LIMIT};
+pub use search::{SearchResult, Filter, FilterError, FacetCount, RankingConfig, ScoreType};
+pub use search::{Explanation, FieldExplanation, MatchExplanation, HighlightSpan, SearchSession};
+pub use matching::MatchConfig;
+pub use error::StoreError;
+pub use lang::{Lang, LangSpec, LangSpecError, Phonetic, CYRILLIC_TO_LATIN};
 pub use lang::{
     lang_german,
     lang_english,
@@ -21,107 +26,487 @@ pub use lang::{
     lang_portuguese,
     lang_russian,
     lang_spanish,
+    detect_lang,
 };
 
 
 thread_local! {
-    static STORES:  RefCell<HashMap<usize, Store>>             = RefCell::new(HashMap::default());
-    static RESULTS: RefCell<HashMap<usize, Vec<SearchResult>>> = RefCell::new(HashMap::default());
+    static STORES:   RefCell<HashMap<usize, Store>>             = RefCell::new(HashMap::default());
+    static RESULTS:  RefCell<HashMap<usize, Vec<SearchResult>>> = RefCell::new(HashMap::default());
+    static FACETS:   RefCell<HashMap<usize, Vec<FacetCount>>>   = RefCell::new(HashMap::default());
+    static SESSIONS: RefCell<HashMap<usize, SearchSession>>     = RefCell::new(HashMap::default());
 }
 
 
-pub fn create_store(id: usize, lang: Lang) {
-    STORES.with(|cell| {
-        let stores = &mut *cell.borrow_mut();
-        if stores.contains_key(&id) {
-            panic!("Duplicate store id");
+pub fn create_store(id: usize, lang: Lang) -> Result<(), StoreError> {
+    let exists = STORES.with(|cell| cell.borrow().contains_key(&id))
+        || RESULTS.with(|cell| cell.borrow().contains_key(&id))
+        || FACETS.with(|cell| cell.borrow().contains_key(&id));
+    if exists {
+        return Err(StoreError::DuplicateStore(id));
+    }
+
+    let mut store = Store::new();
+    store.lang = lang;
+    STORES.with(|cell| cell.borrow_mut().insert(id, store));
+    RESULTS.with(|cell| cell.borrow_mut().insert(id, Vec::with_capacity(DEFAULT_LIMIT)));
+    FACETS.with(|cell| cell.borrow_mut().insert(id, Vec::new()));
+    Ok(())
+}
+
+
+pub fn destroy_store(id: usize) -> Result<(), StoreError> {
+    let store   = STORES.with(|cell| cell.borrow_mut().remove(&id));
+    let results = RESULTS.with(|cell| cell.borrow_mut().remove(&id));
+    let facets  = FACETS.with(|cell| cell.borrow_mut().remove(&id));
+    SESSIONS.with(|cell| cell.borrow_mut().remove(&id));
+    match (store, results, facets) {
+        (Some(_), Some(_), Some(_)) => Ok(()),
+        _ => Err(StoreError::UnknownStore(id)),
+    }
+}
+
+
+pub fn highlight_with(store_id: usize, separators: (&str, &str)) -> Result<(), StoreError> {
+    using_store(store_id, |store| {
+        store.highlight_with(separators);
+    })
+}
+
+
+pub fn set_duplicate_policy(store_id: usize, policy: DuplicatePolicy) -> Result<(), StoreError> {
+    using_store(store_id, |store| {
+        store.duplicates = policy;
+    })
+}
+
+
+pub fn set_ranking(store_id: usize, ranking: RankingConfig) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.ranking = ranking;
+    })
+}
+
+
+pub fn set_matching(store_id: usize, matching: MatchConfig) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.matching = matching;
+    })
+}
+
+
+pub fn set_explain(store_id: usize, explain: bool) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.explain = explain;
+    })
+}
+
+
+pub fn set_facets(store_id: usize, names: &[&str]) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.facets = names.iter().map(|name| name.to_string()).collect();
+    })
+}
+
+
+pub fn set_detect_lang(store_id: usize, detect: bool) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.detect = detect;
+    })
+}
+
+
+/// Count trigrams with candidates of the previous query when the next one extends it, see `SearchSession`.
+pub fn set_incremental(store_id: usize, enable: bool) -> Result<(), StoreError> {
+    using_store(store_id, |_| ())?;
+    SESSIONS.with(|cell| {
+        let sessions = &mut *cell.borrow_mut();
+        if enable {
+            sessions.entry(store_id).or_default();
+        } else {
+            sessions.remove(&store_id);
         }
-        let mut store = Store::new();
-        store.lang = lang;
-        stores.insert(id, store);
     });
+    Ok(())
+}
 
-    RESULTS.with(|cell| {
-        let buffers = &mut *cell.borrow_mut();
-        if buffers.contains_key(&id) {
-            panic!("Duplicate store id");
+
+/// Keep results of `size` recent queries without a filter, 0 (default) disables the cache.
+pub fn set_cache_size(store_id: usize, size: usize) -> Result<(), StoreError> {
+    using_store(store_id, |store| {
+        store.cache.get_mut().unwrap_or_else(PoisonError::into_inner).set_capacity(size);
+    })
+}
+
+
+/// Returns hits and misses of the result cache, see `set_cache_size`.
+pub fn cache_stats(store_id: usize) -> Result<(usize, usize), StoreError> {
+    using_store(store_id, |store| {
+        let cache = store.cache.get_mut().unwrap_or_else(PoisonError::into_inner);
+        (cache.hits, cache.misses)
+    })
+}
+
+
+/// Retry queries finding fewer than `min_hits` with other keyboard layouts, 0 disables retries.
+pub fn set_layout_retry(store_id: usize, min_hits: usize) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.layout_retry = min_hits;
+    })
+}
+
+
+/// Returns the language index to pass to `add_record`, see `Store::add_lang`.
+pub fn add_lang(store_id: usize, lang: Lang) -> Result<usize, StoreError> {
+    configure_store(store_id, |store| {
+        store.add_lang(lang)
+    })
+}
+
+
+/// Synonyms go to every store language, so add languages first.
+pub fn add_synonym(store_id: usize, from: &str, to: &str) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.lang.add_synonym(from, to);
+        for lang in &mut store.langs {
+            lang.add_synonym(from, to);
         }
-        buffers.insert(id, Vec::with_capacity(DEFAULT_LIMIT));
-    });
+    })
 }
 
 
+pub fn add_synonyms(store_id: usize, group: &[&str]) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.lang.add_synonyms(group);
+        for lang in &mut store.langs {
+            lang.add_synonyms(group);
+        }
+    })
+}
 
-pub fn destroy_store(id: usize) {
-    STORES.with(|cell| {
-        let stores = &mut *cell.borrow_mut();
-        if !stores.contains_key(&id) {
-            panic!("Missing store id");
+
+/// Transliteration applies to records added afterwards, so call it before `add_record`.
+pub fn add_transliteration(store_id: usize, from: &str, to: &str) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        store.lang.add_transliteration(from, to);
+        for lang in &mut store.langs {
+            lang.add_transliteration(from, to);
         }
-        stores.remove(&id);
-    });
+    })
+}
 
-    RESULTS.with(|cell| {
-        let buffers = &mut *cell.borrow_mut();
-        if !buffers.contains_key(&id) {
-            panic!("Missing store id");
+
+/// Indexes Cyrillic records and queries in Latin script, see `add_transliteration`.
+pub fn transliterate_cyrillic(store_id: usize) -> Result<(), StoreError> {
+    for (from, to) in CYRILLIC_TO_LATIN {
+        add_transliteration(store_id, from, to)?;
+    }
+    Ok(())
+}
+
+
+/// Match words by sound, with Cologne phonetics for German and Double Metaphone for other languages.
+/// Phonetic keys are computed for records added afterwards, so call it before `add_record`.
+pub fn set_phonetic(store_id: usize, enable: bool) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        for lang in std::iter::once(&mut store.lang).chain(&mut store.langs) {
+            let phonetic = if enable { Some(Phonetic::for_lang(lang.code())) } else { None };
+            lang.set_phonetic(phonetic);
         }
-        buffers.remove(&id);
-    });
+    })
+}
+
+
+/// Make typos on neighbouring keys of each language keyboard, like "vat" for "cat", cheaper than other substitutions.
+pub fn set_key_typos(store_id: usize, enable: bool) -> Result<(), StoreError> {
+    configure_store(store_id, |store| {
+        for lang in std::iter::once(&mut store.lang).chain(&mut store.langs) {
+            lang.set_key_typos(enable);
+        }
+    })
 }
 
 
-pub fn highlight_with(store_id: usize, separators: (&str, &str)) {
+pub fn add_field(store_id: usize, name: &str, weight: f64) -> Result<usize, StoreError> {
+    configure_store(store_id, |store| {
+        store.add_field(name, weight)
+    })
+}
+
+
+/// Without `lang` the record language is detected if enabled by `set_detect_lang`, or the default one.
+/// Fails with `DuplicateRecord` if the record is rejected by `DuplicatePolicy::Reject`,
+/// or with `UnknownLang` for a `lang` not returned by `add_lang`.
+/// `fields` go in the order of `add_field`, missing ones are empty and extra ones are dropped.
+pub fn add_record(store_id: usize, record_id: usize, title: &str, fields: &[&str], rating: usize, lang: Option<usize>) -> Result<(), StoreError> {
     using_store(store_id, |store| {
-        store.highlight_with(separators);
-    });
+        let record = new_record(store, record_id, title, fields, rating, lang)?;
+        if store.add(record) { Ok(()) } else { Err(StoreError::DuplicateRecord(record_id)) }
+    })?
 }
 
 
-pub fn add_record(store_id: usize, record_id: usize, title: &str, rating: usize) {
+pub fn get_record<T, F>(store_id: usize, record_id: usize, f: F) -> Result<T, StoreError> where F: (FnOnce(Option<&Record>) -> T) {
     using_store(store_id, |store| {
-        store.add(Record::new(record_id, title, rating, &store.lang));
+        f(store.get(record_id))
     })
 }
 
 
-pub fn set_limit(store_id: usize, limit: usize)  {
+/// Fails with `UnknownRecord` if no record has the id.
+pub fn remove_record(store_id: usize, record_id: usize) -> Result<(), StoreError> {
     using_store(store_id, |store| {
-    using_results(store_id, |buffer| {
-        store.limit = limit;
-        if limit > buffer.capacity() {
-            buffer.reserve_exact(limit - buffer.len());
+        match store.remove(record_id) {
+            Some(_) => Ok(()),
+            None    => Err(StoreError::UnknownRecord(record_id)),
         }
-    }); });
+    })?
+}
+
+
+/// Fails with `UnknownRecord` if no record has the id,
+/// or with `UnknownLang` for a `lang` not returned by `add_lang`.
+pub fn update_record(store_id: usize, record_id: usize, title: &str, fields: &[&str], rating: usize, lang: Option<usize>) -> Result<(), StoreError> {
+    using_store(store_id, |store| {
+        let record = new_record(store, record_id, title, fields, rating, lang)?;
+        if store.update(record) { Ok(()) } else { Err(StoreError::UnknownRecord(record_id)) }
+    })?
+}
+
+
+pub fn set_record_attr(store_id: usize, record_id: usize, name: &str, value: Attribute) -> Result<bool, StoreError> {
+    using_store(store_id, |store| {
+        store.set_attr(record_id, name, value)
+    })
+}
+
+
+fn new_record(store: &Store, record_id: usize, title: &str, fields: &[&str], rating: usize, lang_ix: Option<usize>) -> Result<Record, StoreError> {
+    let lang_ix = lang_ix
+        .or_else(|| if store.detect { store.detect_lang(title) } else { None })
+        .unwrap_or(0);
+    let lang = store.get_lang(lang_ix).ok_or(StoreError::UnknownLang(lang_ix))?;
+    let mut record = Record::new(record_id, title, rating, lang);
+    for field_ix in 0 .. store.fields.len() {
+        record.add_field(fields.get(field_ix).copied().unwrap_or(""), lang);
+    }
+    record.lang = lang_ix;
+    Ok(record)
+}
+
+
+pub fn export_store(store_id: usize) -> Result<Vec<u8>, StoreError> {
+    using_store(store_id, |store| {
+        store.to_bytes()
+    })
 }
 
 
-pub fn run_search(store_id: usize, query: &str) {
+pub fn import_store(store_id: usize, bytes: &[u8]) -> Result<(), StoreError> {
     using_store(store_id, |store| {
+        store.load_bytes(bytes)
+    })??;
+    Ok(())
+}
+
+
+/// Fails with `InvalidLimit` for zero or a limit too large to allocate results for.
+pub fn set_limit(store_id: usize, limit: usize) -> Result<(), StoreError> {
+    if limit == 0 {
+        return Err(StoreError::InvalidLimit(limit));
+    }
+    let reserved = using_results(store_id, |buffer| {
+        limit <= buffer.capacity() || buffer.try_reserve_exact(limit - buffer.len()).is_ok()
+    })?;
+    if !reserved {
+        return Err(StoreError::InvalidLimit(limit));
+    }
+    configure_store(store_id, |store| {
+        store.limit = limit;
+    })
+}
+
+
+pub fn run_search(store_id: usize, query: &str, filter: Option<&Filter>) -> Result<(), StoreError> {
+    let limit = using_store(store_id, |store| store.limit)?;
+    run_search_page(store_id, query, filter, 0, limit)
+}
+
+
+pub fn run_search_page(store_id: usize, query: &str, filter: Option<&Filter>, offset: usize, limit: usize) -> Result<(), StoreError> {
+    let (results, counts) = using_store(store_id, |store| {
+        SESSIONS.with(|cell| match cell.borrow_mut().get_mut(&store_id) {
+            Some(session) => store.search_session(session, query, filter, offset, limit),
+            None          => store.search_text(query, filter, offset, limit),
+        })
+    })?;
     using_results(store_id, |buffer| {
-        let query = tokenize_query(query, &store.lang);
-        let query = query.to_ref();
         buffer.clear();
-        for result in store.search(&query) {
-            buffer.push(result);
-        }
-    }); });
+        buffer.extend(results);
+    })?;
+    using_facets(store_id, |facets| {
+        *facets = counts;
+    })
 }
 
 
-pub fn using_store<T, F>(store_id: usize, f: F) -> T where F: (FnOnce(&mut Store) -> T) {
+pub fn using_store<T, F>(store_id: usize, f: F) -> Result<T, StoreError> where F: (FnOnce(&mut Store) -> T) {
     STORES.with(|cell| {
         let stores = &mut *cell.borrow_mut();
-        let store  = stores.get_mut(&store_id).unwrap();
-        f(store)
+        let store  = stores.get_mut(&store_id).ok_or(StoreError::UnknownStore(store_id))?;
+        Ok(f(store))
+    })
+}
+
+
+/// Same as `using_store` for changes affecting search results, drops cached ones and started sessions.
+fn configure_store<T, F>(store_id: usize, f: F) -> Result<T, StoreError> where F: (FnOnce(&mut Store) -> T) {
+    using_store(store_id, |store| {
+        let value = f(store);
+        store.config_changed();
+        value
     })
 }
 
 
-pub fn using_results<T, F>(store_id: usize, f: F) -> T where F: (FnOnce(&mut Vec<SearchResult>) -> T) {
+pub fn using_results<T, F>(store_id: usize, f: F) -> Result<T, StoreError> where F: (FnOnce(&mut Vec<SearchResult>) -> T) {
     RESULTS.with(|cell| {
         let buffers = &mut *cell.borrow_mut();
-        let buffer  = buffers.get_mut(&store_id).unwrap();
-        f(buffer)
+        let buffer  = buffers.get_mut(&store_id).ok_or(StoreError::UnknownStore(store_id))?;
+        Ok(f(buffer))
     })
 }
+
+
+pub fn using_facets<T, F>(store_id: usize, f: F) -> Result<T, StoreError> where F: (FnOnce(&mut Vec<FacetCount>) -> T) {
+    FACETS.with(|cell| {
+        let buffers = &mut *cell.borrow_mut();
+        let buffer  = buffers.get_mut(&store_id).ok_or(StoreError::UnknownStore(store_id))?;
+        Ok(f(buffer))
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Lang, StoreError, DuplicatePolicy, SnapshotError};
+    use super::{create_store, destroy_store, add_record, set_duplicate_policy, set_limit, import_store, run_search};
+    use super::{set_incremental, using_results, set_cache_size, cache_stats, highlight_with};
+    use super::{add_field, get_record, set_facets, set_record_attr, using_facets, Attribute};
+    use super::{add_lang, update_record, remove_record};
+
+    #[test]
+    fn api_store_errors() {
+        assert_eq!(create_store(1, Lang::new()), Ok(()));
+        assert_eq!(create_store(1, Lang::new()), Err(StoreError::DuplicateStore(1)));
+        assert_eq!(run_search(2, "foo", None),   Err(StoreError::UnknownStore(2)));
+        assert_eq!(destroy_store(1),             Ok(()));
+        assert_eq!(destroy_store(1),             Err(StoreError::UnknownStore(1)));
+    }
+
+    #[test]
+    fn api_record_errors() {
+        create_store(1, Lang::new()).unwrap();
+        assert_eq!(add_record(1, 10, "Foo", &[], 0, None), Ok(()));
+        assert_eq!(add_record(1, 10, "Foo", &[], 0, None), Ok(()));
+        set_duplicate_policy(1, DuplicatePolicy::Reject).unwrap();
+        assert_eq!(add_record(1, 10, "Foo", &[], 0, None), Err(StoreError::DuplicateRecord(10)));
+        assert_eq!(update_record(1, 20, "Bar", &[], 0, None), Err(StoreError::UnknownRecord(20)));
+        assert_eq!(remove_record(1, 10), Ok(()));
+        assert_eq!(remove_record(1, 10), Ok(()));
+        assert_eq!(remove_record(1, 10), Err(StoreError::UnknownRecord(10)));
+        assert_eq!(import_store(1, b"foo"), Err(StoreError::Snapshot(SnapshotError::UnexpectedEnd)));
+        destroy_store(1).unwrap();
+    }
+
+    #[test]
+    fn api_lang_errors() {
+        create_store(1, Lang::new()).unwrap();
+        assert_eq!(add_lang(1, Lang::new()), Ok(1));
+        assert_eq!(add_record(1, 10, "Foo", &[], 0, Some(1)), Ok(()));
+        assert_eq!(add_record(1, 20, "Bar", &[], 0, Some(2)), Err(StoreError::UnknownLang(2)));
+        assert_eq!(update_record(1, 10, "Foo", &[], 0, Some(2)), Err(StoreError::UnknownLang(2)));
+        assert_eq!(get_record(1, 10, |r| r.map(|r| r.lang)), Ok(Some(1)));
+        assert_eq!(get_record(1, 20, |r| r.is_some()), Ok(false));
+        destroy_store(1).unwrap();
+    }
+
+    #[test]
+    fn api_limit_errors() {
+        create_store(1, Lang::new()).unwrap();
+        assert_eq!(set_limit(1, 20),         Ok(()));
+        assert_eq!(set_limit(1, 0),          Err(StoreError::InvalidLimit(0)));
+        assert_eq!(set_limit(1, usize::MAX), Err(StoreError::InvalidLimit(usize::MAX)));
+        assert_eq!(StoreError::InvalidLimit(0).to_string(), "Invalid limit 0");
+        destroy_store(1).unwrap();
+    }
+
+    #[test]
+    fn api_incremental() {
+        create_store(1, Lang::new()).unwrap();
+        add_record(1, 10, "Electric toothbrush", &[], 0, None).unwrap();
+        add_record(1, 20, "Elegant dress",       &[], 0, None).unwrap();
+        let titles = || using_results(1, |results| results.iter().map(|r| r.title.clone()).collect::<Vec<_>>());
+        assert_eq!(set_incremental(2, true), Err(StoreError::UnknownStore(2)));
+        assert_eq!(set_incremental(1, true), Ok(()));
+        run_search(1, "ele", None).unwrap();
+        assert_eq!(titles(), Ok(vec!["[Ele]gant dress".to_string(), "[Ele]ctric toothbrush".to_string()]));
+        run_search(1, "elec", None).unwrap();
+        assert_eq!(titles(), Ok(vec!["[Elec]tric toothbrush".to_string()]));
+        assert_eq!(set_incremental(1, false), Ok(()));
+        run_search(1, "eleg", None).unwrap();
+        assert_eq!(titles(), Ok(vec!["[Eleg]ant dress".to_string()]));
+        destroy_store(1).unwrap();
+    }
+
+    #[test]
+    fn api_cache() {
+        create_store(1, Lang::new()).unwrap();
+        add_record(1, 10, "Electric toothbrush", &[], 0, None).unwrap();
+        let titles = || using_results(1, |results| results.iter().map(|r| r.title.clone()).collect::<Vec<_>>());
+        assert_eq!(set_cache_size(1, 10), Ok(()));
+        run_search(1, "elec", None).unwrap();
+        run_search(1, "Elec", None).unwrap();
+        assert_eq!(cache_stats(1), Ok((1, 1)));
+        highlight_with(1, ("<", ">")).unwrap();
+        run_search(1, "elec", None).unwrap();
+        assert_eq!(titles(), Ok(vec!["<Elec>tric toothbrush".to_string()]));
+        add_record(1, 20, "Electric kettle", &[], 0, None).unwrap();
+        run_search(1, "elec", None).unwrap();
+        assert_eq!(titles().map(|t| t.len()), Ok(2));
+        set_limit(1, 1).unwrap();
+        run_search(1, "elec", None).unwrap();
+        assert_eq!(titles().map(|t| t.len()), Ok(1));
+        assert_eq!(cache_stats(1), Ok((1, 4)));
+        assert_eq!(cache_stats(2), Err(StoreError::UnknownStore(2)));
+        destroy_store(1).unwrap();
+    }
+
+    #[test]
+    fn api_cache_attrs() {
+        create_store(1, Lang::new()).unwrap();
+        add_record(1, 10, "Electric toothbrush", &[], 0, None).unwrap();
+        set_facets(1, &["color"]).unwrap();
+        assert_eq!(set_cache_size(1, 10), Ok(()));
+        let colors = || using_facets(1, |facets| facets.iter().map(|f| f.value.clone()).collect::<Vec<_>>());
+        run_search(1, "elec", None).unwrap();
+        assert_eq!(colors(), Ok(vec![]));
+        assert_eq!(set_record_attr(1, 10, "color", Attribute::keywords(&["blue"])), Ok(true));
+        run_search(1, "elec", None).unwrap();
+        assert_eq!(colors(), Ok(vec!["blue".to_string()]));
+        assert_eq!(cache_stats(1), Ok((0, 2)));
+        destroy_store(1).unwrap();
+    }
+
+    #[test]
+    fn api_record_fields() {
+        create_store(1, Lang::new()).unwrap();
+        add_field(1, "brand", 1.0).unwrap();
+        add_record(1, 10, "Toothbrush", &[],                0, None).unwrap();
+        add_record(1, 20, "Mailbox",    &["Acme", "Metal"], 0, None).unwrap();
+        let fields = |id| get_record(1, id, |r| r.unwrap().fields.iter().map(|f| f.source.len()).collect::<Vec<_>>());
+        assert_eq!(fields(10), Ok(vec![0]));
+        assert_eq!(fields(20), Ok(vec![4]));
+        destroy_store(1).unwrap();
+    }
+}
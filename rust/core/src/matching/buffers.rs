@@ -0,0 +1,43 @@
+use super::WordMatch;
+use super::damlev::DamerauLevenshtein;
+use super::jaccard::Jaccard;
+
+
+const DEFAULT_CAPACITY: usize = 20;
+
+
+/// Scratch space of `word_match`.
+pub struct WordBuffers {
+    pub damlev:  DamerauLevenshtein,
+    pub jaccard: Jaccard<char>,
+}
+
+
+/// Scratch space of `text_match_synonyms`, reused across the hits of one search.
+/// Every search makes its own, so a store can be queried from many threads at once.
+pub struct MatchBuffers {
+    pub words:    WordBuffers,
+    pub rmatches: Vec<Option<WordMatch>>,
+    pub qmatches: Vec<Option<WordMatch>>,
+}
+
+
+impl WordBuffers {
+    pub fn new() -> Self {
+        Self {
+            damlev:  DamerauLevenshtein::new(),
+            jaccard: Jaccard::new(),
+        }
+    }
+}
+
+
+impl MatchBuffers {
+    pub fn new() -> Self {
+        Self {
+            words:    WordBuffers::new(),
+            rmatches: Vec::with_capacity(DEFAULT_CAPACITY),
+            qmatches: Vec::with_capacity(DEFAULT_CAPACITY),
+        }
+    }
+}
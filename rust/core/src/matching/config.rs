@@ -0,0 +1,77 @@
+/// How tolerant word matching is to typos.
+/// Thresholds are relative: word length difference, share of distinct chars
+/// and Damerau-Levenshtein distance, all divided by the longer word length.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchConfig {
+    pub length_threshold:  f64,
+    pub jaccard_threshold: f64,
+    pub damlev_threshold:  f64,
+    pub cost_trans:        f64,
+    pub cost_double:       f64,
+    pub cost_vowel:        f64,
+    pub cost_notalpha:     f64,
+    pub cost_consonant:    f64,
+    pub cost_default:      f64,
+    pub cost_neighbor:     f64,
+    /// With `false` only exact matches are accepted.
+    pub typos:             bool,
+    /// Every query word matches record words by prefix, not only the unfinished last one.
+    pub prefix_only:       bool,
+}
+
+
+impl MatchConfig {
+    /// Looks up a threshold or cost by its field name, returns `false` if there is no such one.
+    pub fn set_param(&mut self, name: &str, value: f64) -> bool {
+        let param = match name {
+            "length_threshold"  => &mut self.length_threshold,
+            "jaccard_threshold" => &mut self.jaccard_threshold,
+            "damlev_threshold"  => &mut self.damlev_threshold,
+            "cost_trans"        => &mut self.cost_trans,
+            "cost_double"       => &mut self.cost_double,
+            "cost_vowel"        => &mut self.cost_vowel,
+            "cost_notalpha"     => &mut self.cost_notalpha,
+            "cost_consonant"    => &mut self.cost_consonant,
+            "cost_default"      => &mut self.cost_default,
+            "cost_neighbor"     => &mut self.cost_neighbor,
+            _ => return false,
+        };
+        *param = value;
+        true
+    }
+}
+
+
+impl Default for MatchConfig {
+    fn default() -> Self {
+        Self {
+            length_threshold:  0.26,
+            jaccard_threshold: 0.51,
+            damlev_threshold:  0.21,
+            cost_trans:        0.5,
+            cost_double:       0.5,
+            cost_vowel:        0.5,
+            cost_notalpha:     0.5,
+            cost_consonant:    1.0,
+            cost_default:      1.0,
+            cost_neighbor:     0.5,
+            typos:             true,
+            prefix_only:       false,
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::MatchConfig;
+
+    #[test]
+    fn match_config_set_param() {
+        let mut config = MatchConfig::default();
+        assert!(config.set_param("damlev_threshold", 0.3));
+        assert!(config.set_param("cost_vowel", 0.25));
+        assert!(!config.set_param("typos", 0.0));
+        assert_eq!(config, MatchConfig { damlev_threshold: 0.3, cost_vowel: 0.25, ..MatchConfig::default() });
+    }
+}
@@ -2,63 +2,61 @@ mod matrix;
 
 use std::f64;
 use fnv::{FnvHashMap as HashMap};
-use std::cell::RefCell;
-use crate::lang::CharClass;
+use crate::lang::{Lang, CharClass};
 use crate::tokenization::{Word, WordView};
 use matrix::DistMatrix;
+use super::MatchConfig;
 
 
 const DEFAULT_CAPACITY: usize = 20;
 
-const COST_TRANS:     f64 = 0.5;
-const COST_DOUBLE:    f64 = 0.5;
-const COST_VOWEL:     f64 = 0.5;
-const COST_NOTALPHA:  f64 = 0.5;
-const COST_CONSONANT: f64 = 1.0;
-const COST_DEFAULT:   f64 = 1.0;
-
 
 pub struct DamerauLevenshtein {
-    pub dists: RefCell<DistMatrix>,
-    last_i1: RefCell<HashMap<char, usize>>,
-    costs1:  RefCell<Vec<f64>>,
-    costs2:  RefCell<Vec<f64>>,
+    pub dists: DistMatrix,
+    last_i1: HashMap<char, usize>,
+    costs1:  Vec<f64>,
+    costs2:  Vec<f64>,
 }
 
 
 impl DamerauLevenshtein {
     pub fn new() -> Self {
-        let dists   = RefCell::new(DistMatrix::new(DEFAULT_CAPACITY + 2));
-        let last_i1 = RefCell::new(HashMap::with_capacity_and_hasher(DEFAULT_CAPACITY, Default::default()));
-        let costs1  = RefCell::new(Vec::with_capacity(DEFAULT_CAPACITY));
-        let costs2  = RefCell::new(Vec::with_capacity(DEFAULT_CAPACITY));
+        let dists   = DistMatrix::new(DEFAULT_CAPACITY + 2);
+        let last_i1 = HashMap::with_capacity_and_hasher(DEFAULT_CAPACITY, Default::default());
+        let costs1  = Vec::with_capacity(DEFAULT_CAPACITY);
+        let costs2  = Vec::with_capacity(DEFAULT_CAPACITY);
         Self { dists, last_i1, costs1, costs2 }
     }
 
-    fn get_cost(class: &CharClass) -> f64 {
+    fn get_cost(class: &CharClass, config: &MatchConfig) -> f64 {
         match class {
-            CharClass::Consonant => COST_CONSONANT,
-            CharClass::Vowel     => COST_VOWEL,
-            CharClass::NotAlpha  => COST_NOTALPHA,
-            _                    => COST_DEFAULT,
+            CharClass::Consonant => config.cost_consonant,
+            CharClass::Vowel     => config.cost_vowel,
+            CharClass::NotAlpha  => config.cost_notalpha,
+            _                    => config.cost_default,
+        }
+    }
+
+    /// Substituting a neighbour key on the keyboard of `lang` costs `cost_neighbor` per key width.
+    fn get_sub_cost(ch1: char, ch2: char, cost1: f64, cost2: f64, lang: &Lang, config: &MatchConfig) -> f64 {
+        let cost = fmax(cost1, cost2);
+        match lang.key_distance(ch1, ch2) {
+            Some(dist) => fmin(cost, config.cost_neighbor * dist),
+            None       => cost,
         }
     }
 
-    pub fn distance(&self, word1: &WordView, word2: &WordView) -> f64 {
+    pub fn distance(&mut self, word1: &WordView, word2: &WordView, lang: &Lang, config: &MatchConfig) -> f64 {
         let chars1 = word1.chars();
         let chars2 = word2.chars();
 
-        let costs1 = &mut *self.costs1.borrow_mut();
-        let costs2 = &mut *self.costs2.borrow_mut();
+        let Self { dists, last_i1, costs1, costs2 } = self;
         costs1.clear();
         costs2.clear();
-        costs1.extend(word1.classes().iter().map(Self::get_cost));
-        costs2.extend(word2.classes().iter().map(Self::get_cost));
+        costs1.extend(word1.classes().iter().map(|class| Self::get_cost(class, config)));
+        costs2.extend(word2.classes().iter().map(|class| Self::get_cost(class, config)));
 
-        let dists = &mut *self.dists.borrow_mut();
-        dists.prepare(&costs1, &costs2);
-
-        let last_i1 = &mut *self.last_i1.borrow_mut();
+        dists.prepare(costs1, costs2);
         last_i1.clear();
 
         for (i1, &ch1) in chars1.iter().enumerate() {
@@ -66,7 +64,7 @@ impl DamerauLevenshtein {
 
             let cost1        = unsafe { *costs1.get_unchecked(i1) };
             let double1      = i1 > 0 && ch1 == unsafe { *chars1.get_unchecked(i1 - 1) };
-            let cost_double1 = if double1 { COST_DOUBLE } else { COST_DEFAULT };
+            let cost_double1 = if double1 { config.cost_double } else { config.cost_default };
             let cost_del     = fmin(cost1, cost_double1);
 
             for (i2, &ch2) in chars2.iter().enumerate() {
@@ -74,10 +72,10 @@ impl DamerauLevenshtein {
 
                 let cost2        = unsafe { *costs2.get_unchecked(i2) };
                 let double2      = i2 > 0 && ch2 == unsafe { *chars2.get_unchecked(i2 - 1) };
-                let cost_double2 = if double2 { COST_DOUBLE } else { COST_DEFAULT };
+                let cost_double2 = if double2 { config.cost_double } else { config.cost_default };
                 let cost_add     = fmin(cost2, cost_double2);
-                let cost_sub     = if ch1 == ch2 { 0.0 } else { fmax(cost1, cost2) };
-                let cost_trans   = COST_TRANS * ((i1 - l1) + (i2 - l2) + 1) as f64;
+                let cost_sub     = if ch1 == ch2 { 0.0 } else { Self::get_sub_cost(ch1, ch2, cost1, cost2, lang, config) };
+                let cost_trans   = config.cost_trans * ((i1 - l1) + (i2 - l2) + 1) as f64;
 
                 let dist_add   = cost_add   + unsafe { dists.get_unchecked(i1 + 2, i2 + 1) };
                 let dist_del   = cost_del   + unsafe { dists.get_unchecked(i1 + 1, i2 + 2) };
@@ -121,14 +119,17 @@ fn fmax(x1: f64, x2: f64) -> f64 {
 
 #[cfg(test)]
 mod tests {
-    use crate::lang::lang_english;
+    use crate::lang::{Lang, KeyboardLayout, lang_english};
     use crate::tokenization::Text;
+    use super::super::MatchConfig;
     use super::DamerauLevenshtein;
 
 
     #[test]
     fn equality() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         let sample = [
             Text::from_str(""),
             Text::from_str("a"),
@@ -136,13 +137,15 @@ mod tests {
             Text::from_str("abc"),
         ];
         for text in sample.iter() {
-            assert_eq!(damlev.distance(&text.view(0), &text.view(0)), 0.0);
+            assert_eq!(damlev.distance(&text.view(0), &text.view(0), &lang, &config), 0.0);
         }
     }
 
     #[test]
     fn prefix() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         let sample = [
             (0.0, Text::from_str("abc"), Text::from_str("abc")),
             (1.0, Text::from_str("abc"), Text::from_str("ab")),
@@ -150,14 +153,16 @@ mod tests {
             (3.0, Text::from_str("abc"), Text::from_str("")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn add_del_continuous() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         let sample = [
             (1.0, Text::from_str("abc"), Text::from_str("xabc")),
             (2.0, Text::from_str("abc"), Text::from_str("xyabc")),
@@ -172,14 +177,16 @@ mod tests {
             (3.0, Text::from_str("abc"), Text::from_str("abcxyz")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn sub_continuous() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         let sample = [
             (1.0, Text::from_str("abcd"), Text::from_str("xbcd")),
             (2.0, Text::from_str("abcd"), Text::from_str("xycd")),
@@ -193,26 +200,30 @@ mod tests {
             (3.0, Text::from_str("abcd"), Text::from_str("axyz")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn trans_continuous() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         let sample = [
             (0.5, Text::from_str("abcd"), Text::from_str("bacd")), // swap 1 and 2
             (1.0, Text::from_str("abcd"), Text::from_str("badc")), // swap 3 and 4
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn add_del_intermittent() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         let sample = [
             (1.0, Text::from_str("abc"), Text::from_str("xabc")),
             (2.0, Text::from_str("abc"), Text::from_str("xaybc")),
@@ -223,14 +234,16 @@ mod tests {
             (3.0, Text::from_str("abc"), Text::from_str("axbycz")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn sub_intermittent() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         let sample = [
             (1.0, Text::from_str("abcd"), Text::from_str("xbcd")),
             (2.0, Text::from_str("abcd"), Text::from_str("xbyd")),
@@ -239,14 +252,35 @@ mod tests {
             (2.0, Text::from_str("abcd"), Text::from_str("axcy")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
+    #[test]
+    fn sub_keyboard() {
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let mut qwerty = Lang::new();
+        let mut jcuken = Lang::new();
+        let (cat, vat, mat) = (Text::from_str("cat"), Text::from_str("vat"), Text::from_str("mat"));
+        let (kot, koi)      = (Text::from_str("кот"), Text::from_str("кои"));
+        assert_eq!(damlev.distance(&cat.view(0), &vat.view(0), &qwerty, &config), 1.0);
+        qwerty.set_key_typos(true);
+        jcuken.set_key_typos(true);
+        jcuken.set_keyboard(KeyboardLayout::Jcuken);
+        assert_eq!(damlev.distance(&cat.view(0), &vat.view(0), &qwerty, &config), 0.5);
+        assert_eq!(damlev.distance(&cat.view(0), &mat.view(0), &qwerty, &config), 1.0);
+        assert_eq!(damlev.distance(&cat.view(0), &vat.view(0), &jcuken, &config), 1.0);
+        assert_eq!(damlev.distance(&kot.view(0), &koi.view(0), &jcuken, &config), 0.5);
+        assert_eq!(damlev.distance(&kot.view(0), &koi.view(0), &qwerty, &config), 1.0);
+    }
+
     #[test]
     fn growth() {
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
+        let lang   = Lang::new();
         for len in (1..501).step_by(100) {
             let mut s1 = String::with_capacity(len);
             let mut s2 = String::with_capacity(len);
@@ -255,16 +289,17 @@ mod tests {
             let t0 = Text::from_str("");
             let t1 = Text::from_str(&s1);
             let t2 = Text::from_str(&s2);
-            assert_eq!(damlev.distance(&t1.view(0), &t1.view(0)), 0.0);
-            assert_eq!(damlev.distance(&t1.view(0), &t0.view(0)), len as f64);
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), len as f64);
+            assert_eq!(damlev.distance(&t1.view(0), &t1.view(0), &lang, &config), 0.0);
+            assert_eq!(damlev.distance(&t1.view(0), &t0.view(0), &lang, &config), len as f64);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), len as f64);
         }
     }
 
     #[test]
     pub fn add_del_lang_consonant() {
         let lang   = lang_english();
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
         let text   = |s| Text::from_str(s).set_char_classes(&lang);
         let sample = [
             (1.0, text("pink"), text("spink")),
@@ -279,15 +314,16 @@ mod tests {
             (3.0, text("pink"), text("pinkstr")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     pub fn add_del_lang_vowel() {
         let lang   = lang_english();
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
         let text   = |s| Text::from_str(s).set_char_classes(&lang);
         let sample = [
             (0.5, text("pink"), text("opink")),
@@ -302,15 +338,16 @@ mod tests {
             (1.5, text("pink"), text("pinkaio")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn sub_lang() {
         let lang   = lang_english();
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
         let text   = |s| Text::from_str(s).set_char_classes(&lang);
         let sample = [
             (0.5, text("pinky"), text("punky")),
@@ -320,15 +357,16 @@ mod tests {
             (2.0, text("pinky"), text("psnkn")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn add_del_double_lang() {
         let lang   = lang_english();
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
         let text   = |s| Text::from_str(s).set_char_classes(&lang);
         let sample = [
             // vowel end
@@ -345,15 +383,16 @@ mod tests {
             (2.0, text("pinky"),  text("pinkysss")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn sub_double_lang() {
         let lang   = lang_english();
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
         let text   = |s| Text::from_str(s).set_char_classes(&lang);
         let sample = [
             // consonant mid
@@ -376,15 +415,16 @@ mod tests {
             (1.5, text("pinky"), text("pooonky")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 
     #[test]
     fn notalpha_lang() {
         let lang   = lang_english();
-        let damlev = DamerauLevenshtein::new();
+        let mut damlev = DamerauLevenshtein::new();
+        let config = MatchConfig::default();
         let text   = |s| Text::from_str(s).set_char_classes(&lang);
         let sample = [
             (0.5, text("pinky"), text("p_nky")),
@@ -392,8 +432,8 @@ mod tests {
             (2.0, text("pinky"), text("__nk_")),
         ];
         for (d, t1, t2) in sample.iter() {
-            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0)), *d);
-            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0)), *d);
+            assert_eq!(damlev.distance(&t1.view(0), &t2.view(0), &lang, &config), *d);
+            assert_eq!(damlev.distance(&t2.view(0), &t1.view(0), &lang, &config), *d);
         }
     }
 }
@@ -1,5 +1,4 @@
 use std::cmp::Ordering;
-use std::cell::RefCell;
 
 use Ordering::{
     Less,
@@ -37,28 +36,27 @@ pub fn simple_similarity<T: PartialEq + Copy + Ord>(set1: &[T], set2: &[T]) -> f
 
 
 pub struct Jaccard<T: PartialEq + Copy + Ord + Default> {
-    set1: RefCell<Vec<T>>,
-    set2: RefCell<Vec<T>>,
+    set1: Vec<T>,
+    set2: Vec<T>,
 }
 
 
 impl<T: PartialEq + Copy + Ord + Default> Jaccard<T> {
     pub fn new() -> Self {
         Self {
-            set1: RefCell::new(Vec::with_capacity(DEFAULT_CAPACITY)),
-            set2: RefCell::new(Vec::with_capacity(DEFAULT_CAPACITY)),
+            set1: Vec::with_capacity(DEFAULT_CAPACITY),
+            set2: Vec::with_capacity(DEFAULT_CAPACITY),
         }
     }
 
-    pub fn similarity(&self, slice1: &[T], slice2: &[T]) -> f64 {
+    pub fn similarity(&mut self, slice1: &[T], slice2: &[T]) -> f64 {
         match (slice1.len(), slice2.len()) {
             (0, 0) => return 1.0,
             (0, _) => return 0.0,
             (_, 0) => return 0.0,
             (_, _) => { },
         }
-        let set1 = &mut *self.set1.borrow_mut();
-        let set2 = &mut *self.set2.borrow_mut();
+        let Self { set1, set2 } = self;
         set1.resize(slice1.len(), Default::default());
         set2.resize(slice2.len(), Default::default());
         set1.copy_from_slice(&slice1);
@@ -67,10 +65,10 @@ impl<T: PartialEq + Copy + Ord + Default> Jaccard<T> {
         set2.sort_unstable();
         set1.dedup();
         set2.dedup();
-        simple_similarity(&set1, &set2)
+        simple_similarity(set1, set2)
     }
 
-    pub fn rel_dist(&self, slice1: &[T], slice2: &[T]) -> f64 {
+    pub fn rel_dist(&mut self, slice1: &[T], slice2: &[T]) -> f64 {
         1.0 - self.similarity(slice1, slice2)
     }
 }
@@ -88,7 +86,7 @@ mod tests {
 
     #[test]
     fn empty_both() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let s1: &[usize] = &[];
         let s2: &[usize] = &[];
         assert_eq!(round2(jaccard.rel_dist(s1, s2)), 0.0);
@@ -97,7 +95,7 @@ mod tests {
 
     #[test]
     fn empty_one() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let sample = [
             (1.00, vec![], vec![1]),
             (1.00, vec![], vec![1, 2]),
@@ -112,7 +110,7 @@ mod tests {
 
     #[test]
     fn equal() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let sample = [
             vec![1],
             vec![1, 2],
@@ -127,7 +125,7 @@ mod tests {
 
     #[test]
     fn different() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let sample = [
             (1.00, vec![1],          vec![5]),
             (1.00, vec![1, 2],       vec![5, 6]),
@@ -142,7 +140,7 @@ mod tests {
 
     #[test]
     fn partial() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let sample = [
             (0.25, vec![1, 2, 3, 4], vec![1, 2, 3]),
             (0.50, vec![1, 2, 3, 4], vec![1, 2]),
@@ -156,7 +154,7 @@ mod tests {
 
     #[test]
     fn permutated() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let sample = [
             (0.00, vec![1, 2, 3, 4], vec![3, 1, 2, 4]),
             (0.00, vec![1, 2, 3, 4], vec![4, 2, 3, 1]),
@@ -170,7 +168,7 @@ mod tests {
 
     #[test]
     fn repeated() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let sample = [
             (0.75, vec![1, 2, 3, 4], vec![1, 1, 1, 1]),
             (0.50, vec![1, 2, 3, 4], vec![2, 1, 2, 1]),
@@ -184,7 +182,7 @@ mod tests {
 
     #[test]
     fn complex() {
-        let jaccard = Jaccard::new();
+        let mut jaccard = Jaccard::new();
         let sample = [
             (0.40, vec![1, 2, 3, 4], vec![0, 1, 2, 4]),
             (0.40, vec![1, 2, 3, 4], vec![0, 2, 3, 1]),
@@ -1,9 +1,13 @@
+mod config;
+mod buffers;
 mod damlev;
 mod jaccard;
 mod word;
 mod word_match;
 mod text;
 
+pub use config::MatchConfig;
+pub use buffers::{MatchBuffers, WordBuffers};
 pub use word_match::WordMatch;
 pub use word::word_match;
-pub use text::text_match;
+pub use text::text_match_synonyms;
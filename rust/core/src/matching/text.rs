@@ -1,115 +1,181 @@
 use std::cmp::Ordering::{Equal, Less};
-use std::cell::RefCell;
-use crate::tokenization::{Word, TextRef};
-use super::WordMatch;
+use crate::lang::Lang;
+use crate::tokenization::{Word, WordView, TextRef, Synonym};
+use super::{WordMatch, MatchConfig, MatchBuffers};
 use super::word::word_match;
 
-thread_local! {
-    static RMATCHES: RefCell<Vec<Option<WordMatch>>> = RefCell::new(Vec::with_capacity(20));
-    static QMATCHES: RefCell<Vec<Option<WordMatch>>> = RefCell::new(Vec::with_capacity(20));
-}
 
+/// Query words may be matched by any of their synonyms,
+/// then record words matching the synonym are highlighted.
+pub fn text_match_synonyms(
+    rtext:    &TextRef,
+    qtext:    &TextRef,
+    synonyms: &[Synonym],
+    lang:     &Lang,
+    config:   &MatchConfig,
+    buffers:  &mut MatchBuffers,
+) -> (Vec<WordMatch>, Vec<WordMatch>) {
+    let MatchBuffers { words, rmatches, qmatches } = buffers;
+    rmatches.clear();
+    qmatches.clear();
+    rmatches.resize(rtext.words.len(), None);
+    qmatches.resize(qtext.words.len(), None);
+
+    let mut match_words = |rword: &WordView, qword: &WordView| word_match(rword, qword, lang, config, words);
+
+    match_synonyms(rtext, qtext, synonyms, &mut match_words, rmatches, qmatches, true);
+
+    for qword in qtext.words.iter() {
+        if qmatches[qword.offset].is_some() { continue; }
+        let qword = qword.to_view(qtext);
+
+        let mut candidate: Option<(WordMatch, WordMatch)> = None;
+
+        for rword in rtext.words.iter() {
+            if rmatches[rword.offset].is_some() { continue; }
+            let rword = rword.to_view(rtext);
+            let mut stop = false;
+
+            None.or_else(|| {
+                    let rnext = rtext.words.get(rword.offset + 1)?.to_view(rtext);
+                    if qword.len() < rword.len() + rword.dist(&rnext) { return None; }
+                    if rmatches.get(rword.offset + 1)?.is_some() { return None; }
+                    let (rmatch,  qmatch)  = match_words(&rword.join(&rnext), &qword)?;
+                    let (rmatch1, rmatch2) = rmatch.split(&rword, &rnext)?;
+                    let roffset1 = rmatch1.offset;
+                    let roffset2 = rmatch2.offset;
+                    let qoffset  = qmatch.offset;
+                    rmatches[roffset1] = Some(rmatch1);
+                    rmatches[roffset2] = Some(rmatch2);
+                    qmatches[qoffset]  = Some(qmatch);
+                    candidate.take();
+                    stop = true;
+                    Some(())
+                })
+                .or_else(|| {
+                    let qnext = qtext.words.get(qword.offset + 1)?.to_view(qtext);
+                    if rword.len() < qword.len() + qword.dist(&qnext) { return None; }
+                    if qmatches.get(qword.offset + 1)?.is_some() { return None; }
+                    let (rmatch,  qmatch)  = match_words(&rword, &qword.join(&qnext))?;
+                    let (qmatch1, qmatch2) = qmatch.split(&qword, &qnext)?;
+                    let roffset        = rmatch.offset;
+                    let qoffset1       = qmatch1.offset;
+                    let qoffset2       = qmatch2.offset;
+                    rmatches[roffset]  = Some(rmatch);
+                    qmatches[qoffset1] = Some(qmatch1);
+                    qmatches[qoffset2] = Some(qmatch2);
+                    candidate.take();
+                    stop = true;
+                    Some(())
+                })
+                .or_else(|| {
+                    let (rmatch2, qmatch2) = match_words(&rword, &qword)?;
+                    let score2 = rmatch2.match_len() - 2 * (rmatch2.typos.ceil() as usize);
+                    let score1 = candidate
+                        .as_ref()
+                        .map(|(m, _)| m.match_len() - 2 * (m.typos.ceil() as usize))
+                        .unwrap_or(0);
+                    let replace = match (candidate.as_ref(), score1.cmp(&score2)) {
+                        (None, _) => true,
+                        (Some(_), Less) => true,
+                        (Some(_), Equal) if !rmatch2.func => true,
+                        _ => false,
+                    };
+                    if replace {
+                        stop      = !rmatch2.func;
+                        candidate = Some((rmatch2, qmatch2));
+                    }
+                    Some(())
+                });
+
+            if stop {
+                break;
+            }
+        }
 
-pub fn text_match(rtext: &TextRef, qtext: &TextRef) -> (Vec<WordMatch>, Vec<WordMatch>) {
-    RMATCHES.with(|rcell| {
-    QMATCHES.with(|qcell| {
-        let rmatches = &mut *rcell.borrow_mut();
-        let qmatches = &mut *qcell.borrow_mut();
-        rmatches.clear();
-        qmatches.clear();
-        rmatches.resize(rtext.words.len(), None);
-        qmatches.resize(qtext.words.len(), None);
+        if let Some((rmatch, qmatch)) = candidate {
+            let roffset       = rmatch.offset;
+            let qoffset       = qmatch.offset;
+            rmatches[roffset] = Some(rmatch);
+            qmatches[qoffset] = Some(qmatch);
+        }
+    }
 
-        for qword in qtext.words.iter() {
-            if qmatches[qword.offset].is_some() { continue; }
-            let qword = qword.to_view(qtext);
+    match_synonyms(rtext, qtext, synonyms, &mut match_words, rmatches, qmatches, false);
 
-            let mut candidate: Option<(WordMatch, WordMatch)> = None;
-
-            for rword in rtext.words.iter() {
-                if rmatches[rword.offset].is_some() { continue; }
-                let rword = rword.to_view(rtext);
-                let mut stop = false;
-
-                None.or_else(|| {
-                        let rnext = rtext.words.get(rword.offset + 1)?.to_view(rtext);
-                        if qword.len() < rword.len() + rword.dist(&rnext) { return None; }
-                        if rmatches.get(rword.offset + 1)?.is_some() { return None; }
-                        let (rmatch,  qmatch)  = word_match(&rword.join(&rnext), &qword)?;
-                        let (rmatch1, rmatch2) = rmatch.split(&rword, &rnext)?;
-                        let roffset1 = rmatch1.offset;
-                        let roffset2 = rmatch2.offset;
-                        let qoffset  = qmatch.offset;
-                        rmatches[roffset1] = Some(rmatch1);
-                        rmatches[roffset2] = Some(rmatch2);
-                        qmatches[qoffset]  = Some(qmatch);
-                        candidate.take();
-                        stop = true;
-                        Some(())
-                    })
-                    .or_else(|| {
-                        let qnext = qtext.words.get(qword.offset + 1)?.to_view(qtext);
-                        if rword.len() < qword.len() + qword.dist(&qnext) { return None; }
-                        if qmatches.get(qword.offset + 1)?.is_some() { return None; }
-                        let (rmatch,  qmatch)  = word_match(&rword, &qword.join(&qnext))?;
-                        let (qmatch1, qmatch2) = qmatch.split(&qword, &qnext)?;
-                        let roffset        = rmatch.offset;
-                        let qoffset1       = qmatch1.offset;
-                        let qoffset2       = qmatch2.offset;
-                        rmatches[roffset]  = Some(rmatch);
-                        qmatches[qoffset1] = Some(qmatch1);
-                        qmatches[qoffset2] = Some(qmatch2);
-                        candidate.take();
-                        stop = true;
-                        Some(())
-                    })
-                    .or_else(|| {
-                        let (rmatch2, qmatch2) = word_match(&rword, &qword)?;
-                        let score2 = rmatch2.match_len() - 2 * (rmatch2.typos.ceil() as usize);
-                        let score1 = candidate
-                            .as_ref()
-                            .map(|(m, _)| m.match_len() - 2 * (m.typos.ceil() as usize))
-                            .unwrap_or(0);
-                        let replace = match (candidate.as_ref(), score1.cmp(&score2)) {
-                            (None, _) => true,
-                            (Some(_), Less) => true,
-                            (Some(_), Equal) if !rmatch2.func => true,
-                            _ => false,
-                        };
-                        if replace {
-                            stop      = !rmatch2.func;
-                            candidate = Some((rmatch2, qmatch2));
-                        }
-                        Some(())
-                    });
-
-                if stop {
-                    break;
-                }
-            }
+    let rmatches2 = rmatches.drain(..).filter_map(|m| m).collect::<Vec<_>>();
+    let qmatches2 = qmatches.drain(..).filter_map(|m| m).collect::<Vec<_>>();
+
+    (rmatches2, qmatches2)
+}
 
-            if let Some((rmatch, qmatch)) = candidate {
-                let roffset       = rmatch.offset;
-                let qoffset       = qmatch.offset;
-                rmatches[roffset] = Some(rmatch);
-                qmatches[qoffset] = Some(qmatch);
+
+/// Exact synonym matches go before regular ones, so "hoodie" prefers "hooded sweatshirt" to a typo in "hooded",
+/// synonyms with typos are only tried for query words left unmatched.
+fn match_synonyms(
+    rtext:       &TextRef,
+    qtext:       &TextRef,
+    synonyms:    &[Synonym],
+    match_words: &mut impl FnMut(&WordView, &WordView) -> Option<(WordMatch, WordMatch)>,
+    rmatches:    &mut [Option<WordMatch>],
+    qmatches:    &mut [Option<WordMatch>],
+    exact:       bool,
+) {
+    for synonym in synonyms {
+        let (start, end) = synonym.span;
+        if qmatches[start .. end].iter().any(|m| m.is_some()) { continue; }
+        let stext = synonym.text.to_ref();
+        let mut found: Vec<WordMatch> = Vec::with_capacity(stext.words.len());
+
+        for sword in stext.words.iter() {
+            let sword = sword.to_view(&stext);
+            let best  = rtext.words
+                .iter()
+                .filter(|rword| rmatches[rword.offset].is_none())
+                .filter(|rword| found.iter().all(|m| m.offset != rword.offset))
+                .filter_map(|rword| match_words(&rword.to_view(rtext), &sword))
+                .map(|(rmatch, _)| rmatch)
+                .filter(|rmatch| !exact || rmatch.typos <= f64::EPSILON)
+                .min_by(|m1, m2| m1.typos.partial_cmp(&m2.typos).unwrap_or(Equal));
+            match best {
+                Some(rmatch) => found.push(rmatch),
+                None         => { found.clear(); break; },
             }
         }
+        if found.is_empty() { continue; }
 
-        let rmatches2 = rmatches.drain(..).filter_map(|m| m).collect::<Vec<_>>();
-        let qmatches2 = qmatches.drain(..).filter_map(|m| m).collect::<Vec<_>>();
-
-        (rmatches2, qmatches2)
-    }) })
+        let typos = found.iter().map(|m| m.typos).sum::<f64>();
+        for rmatch in found {
+            let roffset       = rmatch.offset;
+            rmatches[roffset] = Some(rmatch);
+        }
+        for qword in &qtext.words[start .. end] {
+            let qword = qword.to_view(qtext);
+            qmatches[qword.offset] = Some(WordMatch {
+                offset:   qword.offset,
+                slice:    qword.slice,
+                subslice: (0, qword.len()),
+                func:     qword.is_function(),
+                typos:    if qword.offset == start { typos } else { 0.0 },
+                fin:      true,
+            });
+        }
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
-    use crate::tokenization::{Text, TextOwn};
-    use crate::lang::{CharClass, lang_basic, lang_english, lang_spanish};
-    use super::{text_match};
+    use crate::tokenization::{Text, TextOwn, TextRef, Synonym};
+    use crate::lang::{Lang, CharClass, lang_basic, lang_english, lang_spanish};
+    use super::super::{WordMatch, MatchConfig, MatchBuffers};
+    use super::text_match_synonyms;
+
+
+    fn text_match(rtext: &TextRef, qtext: &TextRef) -> (Vec<WordMatch>, Vec<WordMatch>) {
+        text_match_synonyms(rtext, qtext, &[], &Lang::new(), &MatchConfig::default(), &mut MatchBuffers::new())
+    }
 
 
     fn text(s: &str) -> TextOwn {
@@ -282,4 +348,21 @@ mod tests {
         assert_debug_snapshot!(text_match(&rtext1.to_ref(), &qtext.to_ref()));
         assert_debug_snapshot!(text_match(&rtext2.to_ref(), &qtext.to_ref()));
     }
+
+    #[test]
+    fn match_text_synonyms() {
+        let rtext    = text("smart television stand");
+        let qtext    = text("big tv").fin(false);
+        let synonyms = [Synonym { span: (1, 2), text: text("television") }];
+        assert_debug_snapshot!(text_match_synonyms(&rtext.to_ref(), &qtext.to_ref(), &synonyms, &Lang::new(), &MatchConfig::default(), &mut MatchBuffers::new()));
+    }
+
+    #[test]
+    fn match_text_synonyms_phrase() {
+        let rtext    = text("warm hooded sweatshirt");
+        let qtext    = text("pullover").fin(false);
+        let synonym1 = Synonym { span: (0, 1), text: text("hooded jacket") };
+        let synonym2 = Synonym { span: (0, 1), text: text("hooded sweatshirt") };
+        assert_debug_snapshot!(text_match_synonyms(&rtext.to_ref(), &qtext.to_ref(), &[synonym1, synonym2], &Lang::new(), &MatchConfig::default(), &mut MatchBuffers::new()));
+    }
 }
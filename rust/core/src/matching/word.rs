@@ -1,85 +1,96 @@
+use crate::lang::Lang;
 use crate::tokenization::{Word, WordView};
-use super::WordMatch;
-use super::damlev::DamerauLevenshtein;
+use super::{WordMatch, MatchConfig, WordBuffers};
 use super::jaccard::Jaccard;
 
 
-const LENGTH_THRESHOLD:  f64 = 0.26;
-const JACCARD_THRESHOLD: f64 = 0.51;
-const DAMLEV_THRESHOLD:  f64 = 0.21;
-
-thread_local! {
-    static DAMLEV:  DamerauLevenshtein = DamerauLevenshtein::new();
-    static JACCARD: Jaccard<char>      = Jaccard::new();
-}
-
-
-pub fn word_match(rword: &WordView, qword: &WordView) -> Option<(WordMatch, WordMatch)> {
+/// Words with equal phonetic keys match as a whole even when too different in spelling,
+/// with all their edits counted as typos, so they rank below regular matches.
+pub fn word_match(
+    rword:   &WordView,
+    qword:   &WordView,
+    lang:    &Lang,
+    config:  &MatchConfig,
+    buffers: &mut WordBuffers,
+) -> Option<(WordMatch, WordMatch)> {
     if qword.is_empty() || rword.is_empty() {
         return None;
     }
-    if !length_check(rword, qword) {
+    let mut unfinished;
+    let qword = if config.prefix_only && qword.fin {
+        unfinished     = qword.clone();
+        unfinished.fin = false;
+        &unfinished
+    } else {
+        qword
+    };
+    let sound_match = config.typos && rword.sound.is_some() && rword.sound == qword.sound;
+    if !sound_match && !length_check(rword, qword, config) {
         return None;
     }
-    if !jaccard_check(rword, qword) {
+    if !sound_match && !jaccard_check(rword, qword, config, &mut buffers.jaccard) {
         return None;
     }
 
-    let mut best_match: Option<(WordMatch, WordMatch)> = None;
+    let damlev = &mut buffers.damlev;
+    damlev.distance(qword, rword, lang, config);
+    let dists = &damlev.dists;
+
+    let left  = if qword.fin { max!(qword.stem, rword.stem) } else { qword.stem } - 1;
+    let right = max!(qword.len(), rword.len()) + 1;
+
+    if right <= left { return None; }
 
-    DAMLEV.with(|damlev| {
-        damlev.distance(qword, rword);
-        let dists = &*damlev.dists.borrow();
-
-        let left  = if qword.fin { max!(qword.stem, rword.stem) } else { qword.stem } - 1;
-        let right = max!(qword.len(), rword.len()) + 1;
-
-        if right <= left { return; }
-
-        let range = (left .. right).rev();
-
-        for rslice in range.clone() {
-            for qslice in range.clone() {
-                // Out of bounds.
-                if qslice > qword.len() { continue; }
-                if rslice > rword.len() { continue; }
-                if qslice < qword.stem  { continue; }
-                // Left margin is for insertion/deletion, not for both prefixes at the same time.
-                if rslice == left && qslice == left  { continue; }
-                // Compare partial words only if query is unfinished.
-                if qword.fin && rslice < rword.stem { break; }
-                // Words with 2+ insertions/deletions are mismatched by default.
-                if (qslice as isize - rslice as isize).abs() > 1 { continue; }
-
-                // Damlev matrix got extra 2 cols/rows, so for chars[i] get row[i+2].
-                let dist = dists.get(qslice + 1, rslice + 1);
-
-                let rel = dist / max!(qslice, rslice, 1) as f64;
-                if rel > DAMLEV_THRESHOLD { continue; }
-
-                best_match = best_match
-                    .take()
-                    .filter(|pair| pair.0.typos <= dist)
-                    .or_else(|| Some(WordMatch::new_pair(
-                        rword,
-                        qword,
-                        rslice,
-                        qslice,
-                        dist,
-                    )));
-
-                if dist <= std::f64::EPSILON {
-                    break;
-                }
+    let mut best_match: Option<(WordMatch, WordMatch)> = None;
+    let range = (left .. right).rev();
+
+    for rslice in range.clone() {
+        for qslice in range.clone() {
+            // Out of bounds.
+            if qslice > qword.len() { continue; }
+            if rslice > rword.len() { continue; }
+            if qslice < qword.stem  { continue; }
+            // Left margin is for insertion/deletion, not for both prefixes at the same time.
+            if rslice == left && qslice == left  { continue; }
+            // Compare partial words only if query is unfinished.
+            if qword.fin && rslice < rword.stem { break; }
+            // Words with 2+ insertions/deletions are mismatched by default.
+            if (qslice as isize - rslice as isize).abs() > 1 { continue; }
+
+            // Damlev matrix got extra 2 cols/rows, so for chars[i] get row[i+2].
+            let dist = dists.get(qslice + 1, rslice + 1);
+
+            let rel = dist / max!(qslice, rslice, 1) as f64;
+            if rel > config.damlev_threshold { continue; }
+            if dist > f64::EPSILON && !config.typos { continue; }
+
+            best_match = best_match
+                .take()
+                .filter(|pair| pair.0.typos <= dist)
+                .or_else(|| Some(WordMatch::new_pair(
+                    rword,
+                    qword,
+                    rslice,
+                    qslice,
+                    dist,
+                )));
+
+            if dist <= std::f64::EPSILON {
+                break;
             }
         }
-    });
+    }
+
+    if best_match.is_none() && sound_match {
+        let dist = dists.get(qword.len() + 1, rword.len() + 1);
+        best_match = Some(WordMatch::new_pair(rword, qword, rword.len(), qword.len(), dist));
+    }
 
     best_match
 }
 
 
-pub fn length_check(rword: &WordView, qword: &WordView) -> bool {
+pub fn length_check(rword: &WordView, qword: &WordView, config: &MatchConfig) -> bool {
     let qlen  = qword.len();
     let rlen  = if qword.fin { rword.len() } else { min!(qlen, rword.len()) };
 
@@ -91,27 +102,34 @@ pub fn length_check(rword: &WordView, qword: &WordView) -> bool {
     let short = min!(qlen, rlen);
     let dist  = 1.0 - (short as f64 / long as f64);
 
-    dist < LENGTH_THRESHOLD
+    dist < config.length_threshold
 }
 
 
-pub fn jaccard_check(rword: &WordView, qword: &WordView) -> bool {
+pub fn jaccard_check(rword: &WordView, qword: &WordView, config: &MatchConfig, jaccard: &mut Jaccard<char>) -> bool {
     let rslice = if qword.fin {
         rword.chars()
     } else {
         &rword.chars()[.. min!(qword.len() + 1, rword.len())]
     };
-    let dist   = JACCARD.with(|j| j.rel_dist(rslice, qword.chars()));
-    dist < JACCARD_THRESHOLD
+    let dist   = jaccard.rel_dist(rslice, qword.chars());
+    dist < config.jaccard_threshold
 }
 
 
 #[cfg(test)]
 mod tests {
     use insta::assert_debug_snapshot;
-    use crate::tokenization::TextOwn;
-    use crate::lang::{Lang, CharClass, lang_english};
-    use super::{length_check, jaccard_check, word_match};
+    use crate::tokenization::{TextOwn, WordView};
+    use crate::lang::{Lang, CharClass, Phonetic, lang_english};
+    use super::super::{WordMatch, MatchConfig, WordBuffers};
+    use super::super::jaccard::Jaccard;
+    use super::{length_check, jaccard_check};
+
+
+    fn word_match(rword: &WordView, qword: &WordView) -> Option<(WordMatch, WordMatch)> {
+        super::word_match(rword, qword, &Lang::new(), &MatchConfig::default(), &mut WordBuffers::new())
+    }
 
 
     fn text(s: &str) -> TextOwn {
@@ -133,7 +151,7 @@ mod tests {
         for &(expect, query) in sample.iter() {
             let rtext  = text("mail");
             let qtext  = text(query);
-            let result = length_check(&rtext.view(0), &qtext.view(0));
+            let result = length_check(&rtext.view(0), &qtext.view(0), &MatchConfig::default());
             assert_eq!(result, expect, "Failed length_check(\"mail\", \"{}\") == {}", query, expect);
         }
     }
@@ -155,7 +173,7 @@ mod tests {
         for &(expect, query) in sample.iter() {
             let rtext  = text("mailbox");
             let qtext  = text(query);
-            let result = length_check(&rtext.view(0), &qtext.view(0));
+            let result = length_check(&rtext.view(0), &qtext.view(0), &MatchConfig::default());
             assert_eq!(result, expect, "Failed length_check(\"mailbox\", \"{}\") == {}", query, expect);
         }
     }
@@ -172,7 +190,7 @@ mod tests {
         for &(expect, query) in sample.iter() {
             let rtext  = text("mail");
             let qtext  = text(query);
-            let result = jaccard_check(&rtext.view(0), &qtext.view(0));
+            let result = jaccard_check(&rtext.view(0), &qtext.view(0), &MatchConfig::default(), &mut Jaccard::new());
             assert_eq!(result, expect, "Failed jaccard_check(\"mail\", \"{}\") == {}", query, expect);
         }
     }
@@ -189,7 +207,7 @@ mod tests {
         for &(expect, query) in sample.iter() {
             let rtext  = text("mailbox");
             let qtext  = text(query);
-            let result = jaccard_check(&rtext.view(0), &qtext.view(0));
+            let result = jaccard_check(&rtext.view(0), &qtext.view(0), &MatchConfig::default(), &mut Jaccard::new());
             assert_eq!(result, expect, "Failed jaccard_check(\"mailbox\", \"{}\") == {}", query, expect);
         }
     }
@@ -206,7 +224,7 @@ mod tests {
         for &(expect, query) in sample.iter() {
             let rtext  = text("mailbox");
             let qtext  = text(query);
-            let result = jaccard_check(&rtext.view(0), &qtext.view(0));
+            let result = jaccard_check(&rtext.view(0), &qtext.view(0), &MatchConfig::default(), &mut Jaccard::new());
             assert_eq!(result, expect, "Failed jaccard_check(\"mailbox\", \"{}\") == {}", query, expect);
         }
     }
@@ -227,7 +245,7 @@ mod tests {
         for &(expect, query) in sample.iter() {
             let rtext  = text("mail");
             let qtext  = text(query).fin(false);
-            let result = jaccard_check(&rtext.view(0), &qtext.view(0));
+            let result = jaccard_check(&rtext.view(0), &qtext.view(0), &MatchConfig::default(), &mut Jaccard::new());
             assert_eq!(result, expect, "Failed jaccard_check(\"mail\", \"{}\") == {}", query, expect);
         }
     }
@@ -246,7 +264,7 @@ mod tests {
         for &(expect, query) in sample.iter() {
             let rtext  = text("mail");
             let qtext  = text(query).fin(false);
-            let result = length_check(&rtext.view(0), &qtext.view(0));
+            let result = length_check(&rtext.view(0), &qtext.view(0), &MatchConfig::default());
             assert_eq!(result, expect, "Failed length_check(\"mail\", \"{}\") == {}", query, expect);
         }
     }
@@ -366,4 +384,36 @@ mod tests {
         let rtext  = TextOwn::from_str("mailbox");
         assert_debug_snapshot!(word_match(&rtext.view(0), &qtext.view(0)));
     }
+
+
+    #[test]
+    fn match_word_phonetic() {
+        let mut lang = Lang::new();
+        lang.set_phonetic(Some(Phonetic::DoubleMetaphone));
+        let qtext  = TextOwn::from_str("jon").set_sound(&lang);
+        let rtext1 = TextOwn::from_str("john").set_sound(&lang);
+        let rtext2 = TextOwn::from_str("john");
+        let rtext3 = TextOwn::from_str("jack").set_sound(&lang);
+        let (rmatch, _) = word_match(&rtext1.view(0), &qtext.view(0)).unwrap();
+        assert_eq!((rmatch.subslice, rmatch.typos), ((0, 4), 1.0));
+        assert_eq!(word_match(&rtext2.view(0), &qtext.view(0)), None);
+        assert_eq!(word_match(&rtext3.view(0), &qtext.view(0)), None);
+    }
+
+
+    #[test]
+    fn match_word_keyboard_neighbor() {
+        let qtext1 = TextOwn::from_str("vat");
+        let qtext2 = TextOwn::from_str("mat");
+        let rtext  = TextOwn::from_str("cat");
+        assert_eq!(word_match(&rtext.view(0), &qtext1.view(0)), None);
+        let mut lang = Lang::new();
+        lang.set_key_typos(true);
+        let key_match = |rword: &WordView, qword: &WordView| {
+            super::word_match(rword, qword, &lang, &MatchConfig::default(), &mut WordBuffers::new())
+        };
+        let (rmatch, _) = key_match(&rtext.view(0), &qtext1.view(0)).unwrap();
+        assert_eq!(rmatch.typos, 0.5);
+        assert_eq!(key_match(&rtext.view(0), &qtext2.view(0)), None);
+    }
 }
@@ -0,0 +1,272 @@
+use std::fmt;
+use std::error::Error;
+use std::iter::Peekable;
+use std::str::CharIndices;
+use crate::store::{Record, Attribute};
+
+
+/// Restriction on record attributes, checked before ranking.
+/// Records missing an attribute never match a condition on it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Filter {
+    /// Integer attribute within an inclusive range.
+    Range(String, i64, i64),
+    /// Keyword set attribute containing a keyword.
+    Keyword(String, String),
+    And(Vec<Filter>),
+    Or(Vec<Filter>),
+    Not(Box<Filter>),
+}
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FilterError {
+    UnexpectedEnd,
+    UnexpectedChar(usize, char),
+    InvalidInteger(usize),
+}
+
+
+impl fmt::Display for FilterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FilterError::UnexpectedEnd           => write!(f, "Unexpected end of filter"),
+            FilterError::UnexpectedChar(pos, ch) => write!(f, "Unexpected {:?} at {} in filter", ch, pos),
+            FilterError::InvalidInteger(pos)     => write!(f, "Invalid integer at {} in filter", pos),
+        }
+    }
+}
+
+
+impl Error for FilterError { }
+
+
+impl Filter {
+    pub fn eq(name: &str, value: i64) -> Self {
+        Filter::Range(name.to_string(), value, value)
+    }
+
+    pub fn keyword(name: &str, keyword: &str) -> Self {
+        Filter::Keyword(name.to_string(), keyword.to_string())
+    }
+
+    pub fn matches(&self, record: &Record) -> bool {
+        match self {
+            Filter::Range(name, min, max) => {
+                let value = record.attr(name).and_then(Attribute::as_int);
+                matches!(value, Some(value) if *min <= value && value <= *max)
+            },
+            Filter::Keyword(name, keyword) => {
+                matches!(record.attr(name), Some(attr) if attr.has_keyword(keyword))
+            },
+            Filter::And(filters) => filters.iter().all(|f| f.matches(record)),
+            Filter::Or(filters)  => filters.iter().any(|f| f.matches(record)),
+            Filter::Not(filter)  => !filter.matches(record),
+        }
+    }
+
+    /// Parses expressions like `in_stock = 1 & (size >= 40 | category : shoes) & !brand : "no name"`.
+    /// Integer attributes are compared with `= != < <= > >=`, keyword sets are checked with `:`.
+    /// `!` binds tighter than `&`, which binds tighter than `|`.
+    pub fn parse(source: &str) -> Result<Self, FilterError> {
+        let mut parser = Parser { chars: source.char_indices().peekable() };
+        let filter = parser.or()?;
+        parser.skip_spaces();
+        match parser.chars.next() {
+            Some((pos, ch)) => Err(FilterError::UnexpectedChar(pos, ch)),
+            None            => Ok(filter),
+        }
+    }
+}
+
+
+struct Parser<'a> {
+    chars: Peekable<CharIndices<'a>>,
+}
+
+
+impl<'a> Parser<'a> {
+    fn or(&mut self) -> Result<Filter, FilterError> {
+        let mut filters = vec![self.and()?];
+        while self.eat('|') {
+            filters.push(self.and()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::Or(filters) })
+    }
+
+    fn and(&mut self) -> Result<Filter, FilterError> {
+        let mut filters = vec![self.unary()?];
+        while self.eat('&') {
+            filters.push(self.unary()?);
+        }
+        Ok(if filters.len() == 1 { filters.remove(0) } else { Filter::And(filters) })
+    }
+
+    fn unary(&mut self) -> Result<Filter, FilterError> {
+        if self.eat('!') {
+            return Ok(Filter::Not(Box::new(self.unary()?)));
+        }
+        if self.eat('(') {
+            let filter = self.or()?;
+            return if self.eat(')') { Ok(filter) } else { Err(self.unexpected()) };
+        }
+        self.condition()
+    }
+
+    fn condition(&mut self) -> Result<Filter, FilterError> {
+        let name = self.token()?;
+        if self.eat(':') {
+            let keyword = self.token()?;
+            return Ok(Filter::Keyword(name, keyword));
+        }
+        let (op, pos) = match self.peek() {
+            Some((pos, ch)) if "=!<>".contains(ch) => {
+                self.chars.next();
+                let eq = self.chars.next_if(|&(_, ch)| ch == '=').is_some();
+                ((ch, eq), pos)
+            },
+            _ => return Err(self.unexpected()),
+        };
+        let value = self.integer()?;
+        let filter = match op {
+            ('=', false) => Filter::Range(name, value, value),
+            ('!', true)  => Filter::Not(Box::new(Filter::Range(name, value, value))),
+            ('<', false) => Filter::Range(name, i64::MIN, value.saturating_sub(1)),
+            ('<', true)  => Filter::Range(name, i64::MIN, value),
+            ('>', false) => Filter::Range(name, value.saturating_add(1), i64::MAX),
+            ('>', true)  => Filter::Range(name, value, i64::MAX),
+            (ch, _)      => return Err(FilterError::UnexpectedChar(pos, ch)),
+        };
+        Ok(filter)
+    }
+
+    fn integer(&mut self) -> Result<i64, FilterError> {
+        let pos = self.peek().map(|(pos, _)| pos).ok_or(FilterError::UnexpectedEnd)?;
+        self.token()?
+            .parse()
+            .map_err(|_| FilterError::InvalidInteger(pos))
+    }
+
+    /// Either a bare word or a double-quoted string.
+    fn token(&mut self) -> Result<String, FilterError> {
+        let mut token = String::new();
+        match self.peek() {
+            None => return Err(FilterError::UnexpectedEnd),
+            Some((_, '"')) => {
+                self.chars.next();
+                loop {
+                    match self.chars.next() {
+                        Some((_, '"')) => return Ok(token),
+                        Some((_, ch))  => token.push(ch),
+                        None           => return Err(FilterError::UnexpectedEnd),
+                    }
+                }
+            },
+            Some(_) => {
+                while let Some(&(_, ch)) = self.chars.peek() {
+                    if !is_word_char(ch) { break; }
+                    token.push(ch);
+                    self.chars.next();
+                }
+            },
+        }
+        if token.is_empty() {
+            return Err(self.unexpected());
+        }
+        Ok(token)
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        match self.peek() {
+            Some((_, ch)) if ch == expected => {
+                self.chars.next();
+                true
+            },
+            _ => false,
+        }
+    }
+
+    fn peek(&mut self) -> Option<(usize, char)> {
+        self.skip_spaces();
+        self.chars.peek().copied()
+    }
+
+    fn skip_spaces(&mut self) {
+        while self.chars.next_if(|&(_, ch)| ch.is_whitespace()).is_some() { }
+    }
+
+    fn unexpected(&mut self) -> FilterError {
+        match self.peek() {
+            Some((pos, ch)) => FilterError::UnexpectedChar(pos, ch),
+            None            => FilterError::UnexpectedEnd,
+        }
+    }
+}
+
+
+fn is_word_char(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '-' || ch == '.'
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::Lang;
+    use crate::store::{Record, Attribute};
+    use super::{Filter, FilterError};
+
+    fn get_record() -> Record {
+        let mut record = Record::new(10, "running shoes", 10, &Lang::new());
+        record.set_attr("in_stock", Attribute::Int(1));
+        record.set_attr("size", Attribute::Int(42));
+        record.set_attr("category", Attribute::keywords(&["shoes", "sport wear"]));
+        record
+    }
+
+    fn check(source: &str) -> bool {
+        Filter::parse(source).unwrap().matches(&get_record())
+    }
+
+    #[test]
+    fn filter_parse_basic() {
+        assert_eq!(
+            Filter::parse("in_stock = 1 & (size >= 40 | !category : shoes)"),
+            Ok(Filter::And(vec![
+                Filter::eq("in_stock", 1),
+                Filter::Or(vec![
+                    Filter::Range("size".to_string(), 40, i64::MAX),
+                    Filter::Not(Box::new(Filter::keyword("category", "shoes"))),
+                ]),
+            ])),
+        );
+    }
+
+    #[test]
+    fn filter_parse_errors() {
+        assert_eq!(Filter::parse(""),               Err(FilterError::UnexpectedEnd));
+        assert_eq!(Filter::parse("size >"),         Err(FilterError::UnexpectedEnd));
+        assert_eq!(Filter::parse("size > big"),     Err(FilterError::InvalidInteger(7)));
+        assert_eq!(Filter::parse("size ~ 1"),       Err(FilterError::UnexpectedChar(5, '~')));
+        assert_eq!(Filter::parse("(size = 1"),      Err(FilterError::UnexpectedEnd));
+        assert_eq!(Filter::parse("size = 1 size"),  Err(FilterError::UnexpectedChar(9, 's')));
+    }
+
+    #[test]
+    fn filter_matches_int() {
+        assert!(check("size = 42"));
+        assert!(check("size != 41"));
+        assert!(check("size > 41 & size < 43"));
+        assert!(!check("size <= 41"));
+        assert!(!check("price < 100"));
+        assert!(check("!price < 100"));
+    }
+
+    #[test]
+    fn filter_matches_keywords() {
+        assert!(check("category : shoes"));
+        assert!(check("category : \"sport wear\""));
+        assert!(!check("category : boots"));
+        assert!(!check("size : shoes"));
+        assert!(!check("category = 1"));
+    }
+}
@@ -0,0 +1,171 @@
+use fnv::{FnvHashMap as HashMap};
+use super::{SearchResult, FacetCount};
+
+
+type CacheKey = (String, usize, usize);
+
+struct CacheEntry {
+    used:    usize,
+    results: Vec<SearchResult>,
+    facets:  Vec<FacetCount>,
+}
+
+
+/// Least recently used pages of `Store::search_text`, keyed by the normalized query, offset and limit.
+/// Entries are dropped when records change, other invalidation is up to the store, see `Store::config_changed`.
+pub struct ResultCache {
+    capacity:   usize,
+    version:    usize,
+    used:       usize,
+    entries:    HashMap<CacheKey, CacheEntry>,
+    pub hits:   usize,
+    pub misses: usize,
+}
+
+
+impl ResultCache {
+    /// Zero capacity disables the cache.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            version:  0,
+            used:     0,
+            entries:  HashMap::default(),
+            hits:     0,
+            misses:   0,
+        }
+    }
+
+    /// Drops the least recently used entries if there are more than `capacity`.
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity;
+        while self.entries.len() > capacity {
+            self.evict();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Entries cached for another `version` of the store records are dropped.
+    pub fn get(&mut self, version: usize, query: &str, offset: usize, limit: usize) -> Option<(Vec<SearchResult>, Vec<FacetCount>)> {
+        if self.capacity == 0 {
+            return None;
+        }
+        if self.version != version {
+            self.entries.clear();
+            self.version = version;
+        }
+        self.used += 1;
+        match self.entries.get_mut(&(normalize_query(query), offset, limit)) {
+            Some(entry) => {
+                self.hits += 1;
+                entry.used = self.used;
+                Some((entry.results.clone(), entry.facets.clone()))
+            },
+            None => {
+                self.misses += 1;
+                None
+            },
+        }
+    }
+
+    pub fn put(&mut self, version: usize, query: &str, offset: usize, limit: usize, found: &(Vec<SearchResult>, Vec<FacetCount>)) {
+        if self.capacity == 0 || self.version != version {
+            return;
+        }
+        let key = (normalize_query(query), offset, limit);
+        if !self.entries.contains_key(&key) && self.entries.len() >= self.capacity {
+            self.evict();
+        }
+        let (results, facets) = found;
+        self.entries.insert(key, CacheEntry { used: self.used, results: results.clone(), facets: facets.clone() });
+    }
+
+    // Linear in the number of entries, which is fine for the few dozen queries the cache is meant for.
+    fn evict(&mut self) {
+        let oldest = self.entries
+            .iter()
+            .min_by_key(|(_, entry)| entry.used)
+            .map(|(key, _)| key.clone());
+        if let Some(key) = oldest {
+            self.entries.remove(&key);
+        }
+    }
+}
+
+
+/// Lowercase query with whitespace runs turned into a single space.
+/// Trailing whitespace is kept, as it marks the last word finished.
+fn normalize_query(query: &str) -> String {
+    let mut normalized = String::with_capacity(query.len());
+    let mut space      = false;
+    for ch in query.trim_start().chars() {
+        if ch.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space {
+            normalized.push(' ');
+            space = false;
+        }
+        normalized.extend(ch.to_lowercase());
+    }
+    if space {
+        normalized.push(' ');
+    }
+    normalized
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::search::{SearchResult, FacetCount};
+    use super::{ResultCache, normalize_query};
+
+    fn page(ids: &[usize]) -> (Vec<SearchResult>, Vec<FacetCount>) {
+        let results = ids
+            .iter()
+            .map(|&id| SearchResult {
+                id,
+                title:       String::new(),
+                fields:      Vec::new(),
+                spans:       Vec::new(),
+                field_spans: Vec::new(),
+                explain:     None,
+                correction:  None,
+            })
+            .collect();
+        (results, Vec::new())
+    }
+
+    fn get_ids(cache: &mut ResultCache, version: usize, query: &str) -> Option<Vec<usize>> {
+        cache.get(version, query, 0, 10).map(|(results, _)| results.iter().map(|r| r.id).collect())
+    }
+
+    #[test]
+    fn cache_normalize_query() {
+        assert_eq!(normalize_query("  Metal\t MAILbox"), "metal mailbox");
+        assert_eq!(normalize_query("metal  "),           "metal ");
+        assert_eq!(normalize_query(""),                  "");
+    }
+
+    #[test]
+    fn cache_lru() {
+        let mut cache = ResultCache::new(2);
+        assert_eq!(get_ids(&mut cache, 0, "foo"), None);
+        cache.put(0, "foo", 0, 10, &page(&[1]));
+        cache.put(0, "bar", 0, 10, &page(&[2]));
+        assert_eq!(get_ids(&mut cache, 0, "FOO"), Some(vec![1]));
+        cache.put(0, "baz", 0, 10, &page(&[3]));
+        assert_eq!(get_ids(&mut cache, 0, "bar"), None);
+        assert_eq!(get_ids(&mut cache, 0, "foo"), Some(vec![1]));
+        assert_eq!(get_ids(&mut cache, 0, "baz"), Some(vec![3]));
+        assert_eq!((cache.hits, cache.misses), (3, 2));
+        cache.set_capacity(1);
+        assert_eq!(get_ids(&mut cache, 0, "baz"), Some(vec![3]));
+        assert_eq!(get_ids(&mut cache, 1, "baz"), None);
+        assert_eq!(cache.entries.len(), 0);
+    }
+}
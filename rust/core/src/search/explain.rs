@@ -0,0 +1,82 @@
+use crate::tokenization::TextRef;
+use crate::matching::WordMatch;
+use crate::store::Field;
+use crate::search::{Hit, ScoreType};
+
+
+/// Why a hit was ranked the way it was, filled when `Store::explain` is set.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Explanation {
+    pub scores:     Vec<(&'static str, isize)>,
+    pub rmatches:   Vec<MatchExplanation>,
+    pub qmatches:   Vec<MatchExplanation>,
+    /// Matches of the record fields, in the order of store fields.
+    pub fields:     Vec<FieldExplanation>,
+    /// Query trigrams found in the record.
+    pub grams:      usize,
+    /// Records picked from the trigram index for the query.
+    pub candidates: usize,
+}
+
+
+/// Matched words of a record field and of the query in it.
+/// A query word matching several fields is scored for the one with the highest weight.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldExplanation {
+    pub name:     String,
+    pub weight:   f64,
+    pub rmatches: Vec<MatchExplanation>,
+    pub qmatches: Vec<MatchExplanation>,
+}
+
+
+/// A matched word of a record title or a query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchExplanation {
+    pub word:  String,
+    /// Number of leading chars matched, less than the word length for prefixes.
+    pub len:   usize,
+    pub typos: f64,
+    pub func:  bool,
+    pub fin:   bool,
+}
+
+
+pub fn explain(query: &TextRef, hit: &Hit, fields: &[Field], grams: usize, candidates: usize) -> Explanation {
+    let fields = hit.fields
+        .iter()
+        .zip(fields)
+        .map(|(field, store_field)| {
+            FieldExplanation {
+                name:     store_field.name.clone(),
+                weight:   field.weight,
+                rmatches: explain_matches(&field.text, &field.rmatches),
+                qmatches: explain_matches(query, &field.qmatches),
+            }
+        })
+        .collect();
+    Explanation {
+        scores:   ScoreType::ALL.iter().map(|&score| (score.name(), hit.scores[score])).collect(),
+        rmatches: explain_matches(&hit.title, &hit.rmatches),
+        qmatches: explain_matches(query, &hit.qmatches),
+        fields,
+        grams,
+        candidates,
+    }
+}
+
+
+fn explain_matches(text: &TextRef, matches: &[WordMatch]) -> Vec<MatchExplanation> {
+    matches
+        .iter()
+        .map(|m| {
+            MatchExplanation {
+                word:  text.source[m.slice.0 .. m.slice.1].iter().filter(|&&ch| ch != '\0').collect(),
+                len:   m.match_len(),
+                typos: m.typos,
+                func:  m.func,
+                fin:   m.fin,
+            }
+        })
+        .collect()
+}
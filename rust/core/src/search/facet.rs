@@ -0,0 +1,93 @@
+use fnv::{FnvHashMap as HashMap};
+use crate::store::{Record, Attribute};
+
+
+/// Number of hits having a value of a faceted attribute.
+/// Integer values are formatted as decimal strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FacetCount {
+    pub name:  String,
+    pub value: String,
+    pub count: usize,
+}
+
+
+/// Counts are grouped by facet in the order of `names`,
+/// most frequent values go first.
+pub fn count_facets<'a>(
+    names:   &[String],
+    records: impl Iterator<Item=&'a Record>,
+) -> Vec<FacetCount> {
+    if names.is_empty() {
+        return Vec::new();
+    }
+
+    let mut counts: HashMap<(usize, String), usize> = HashMap::default();
+    for record in records {
+        for (name_ix, name) in names.iter().enumerate() {
+            match record.attr(name) {
+                Some(Attribute::Int(value)) => {
+                    *counts.entry((name_ix, value.to_string())).or_default() += 1;
+                },
+                Some(Attribute::Keywords(keywords)) => {
+                    for (i, keyword) in keywords.iter().enumerate() {
+                        if keywords[..i].contains(keyword) { continue; }
+                        *counts.entry((name_ix, keyword.clone())).or_default() += 1;
+                    }
+                },
+                None => { },
+            }
+        }
+    }
+
+    let mut counts = counts.into_iter().collect::<Vec<_>>();
+    counts.sort_unstable_by(|((ix1, value1), count1), ((ix2, value2), count2)| {
+        ix1.cmp(ix2)
+            .then_with(|| count2.cmp(count1))
+            .then_with(|| value1.cmp(value2))
+    });
+    counts
+        .into_iter()
+        .map(|((name_ix, value), count)| {
+            FacetCount { name: names[name_ix].clone(), value, count }
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::Lang;
+    use crate::store::{Record, Attribute};
+    use super::{count_facets, FacetCount};
+
+    fn get_record(id: usize, size: i64, tags: &[&str]) -> Record {
+        let mut record = Record::new(id, "shoes", 0, &Lang::new());
+        record.set_attr("size", Attribute::Int(size));
+        if !tags.is_empty() {
+            record.set_attr("tags", Attribute::keywords(tags));
+        }
+        record
+    }
+
+    fn facet(name: &str, value: &str, count: usize) -> FacetCount {
+        FacetCount { name: name.to_string(), value: value.to_string(), count }
+    }
+
+    #[test]
+    fn count_facets_basic() {
+        let records = [
+            get_record(10, 42, &["sport", "summer"]),
+            get_record(20, 40, &["sport", "sport"]),
+            get_record(30, 42, &[]),
+        ];
+        let names  = ["tags".to_string(), "size".to_string(), "color".to_string()];
+        let counts = count_facets(&names, records.iter());
+        assert_eq!(counts, vec![
+            facet("tags", "sport",  2),
+            facet("tags", "summer", 1),
+            facet("size", "42",     2),
+            facet("size", "40",     1),
+        ]);
+    }
+}
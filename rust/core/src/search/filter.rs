@@ -1,12 +1,18 @@
 use crate::tokenization::TextRef;
+use crate::matching::WordMatch;
 use crate::search::Hit;
 
 pub fn hit_matches(query: &TextRef, hit: &Hit) -> bool {
     if query.is_empty() { return true; }
-    if hit.rmatches.len() == 0 { return false; }
-    if hit.rmatches.len() == 1 && hit.qmatches.len() == 1 && query.words.len() > 1 {
-        let rmatch     = &hit.rmatches[0];
-        let qmatch     = &hit.qmatches[0];
+    if text_matches(query, &hit.rmatches, &hit.qmatches) { return true; }
+    hit.fields.iter().any(|f| text_matches(query, &f.rmatches, &f.qmatches))
+}
+
+fn text_matches(query: &TextRef, rmatches: &[WordMatch], qmatches: &[WordMatch]) -> bool {
+    if rmatches.is_empty() { return false; }
+    if rmatches.len() == 1 && qmatches.len() == 1 && query.words.len() > 1 {
+        let rmatch     = &rmatches[0];
+        let qmatch     = &qmatches[0];
         let first_half = (qmatch.word_len() * 2) < rmatch.word_len();
         if !rmatch.fin && first_half { return false; }
     }
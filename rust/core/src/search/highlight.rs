@@ -1,14 +1,24 @@
-use crate::tokenization::Text;
+use crate::tokenization::{Text, TextRef};
+use crate::matching::WordMatch;
 use crate::search::Hit;
 
 
 pub fn highlight(hit: &Hit, dividers: (&[char], &[char])) -> String {
+    highlight_text(&hit.title, &hit.rmatches, dividers)
+}
+
+
+pub fn highlight_fields(hit: &Hit, dividers: (&[char], &[char])) -> Vec<String> {
+    hit.fields
+        .iter()
+        .map(|field| highlight_text(&field.text, &field.rmatches, dividers))
+        .collect()
+}
+
+
+pub fn highlight_text(text: &TextRef, rmatches: &[WordMatch], dividers: (&[char], &[char])) -> String {
     let (div_left, div_right) = dividers;
-    let Hit {
-        title: Text { words, source, .. },
-        rmatches,
-        ..
-    } = hit;
+    let Text { words, source, .. } = text;
 
     let mut highlighted = {
         let chars_src = source.len();
@@ -41,13 +51,67 @@ pub fn highlight(hit: &Hit, dividers: (&[char], &[char])) -> String {
 }
 
 
+/// Matched range of an original text, as offsets in chars, UTF-8 bytes and UTF-16 code units.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HighlightSpan {
+    pub chars: (usize, usize),
+    pub bytes: (usize, usize),
+    pub utf16: (usize, usize),
+}
+
+
+pub fn highlight_spans(hit: &Hit) -> Vec<HighlightSpan> {
+    highlight_text_spans(&hit.title, &hit.rmatches)
+}
+
+
+pub fn highlight_field_spans(hit: &Hit) -> Vec<Vec<HighlightSpan>> {
+    hit.fields
+        .iter()
+        .map(|field| highlight_text_spans(&field.text, &field.rmatches))
+        .collect()
+}
+
+
+pub fn highlight_text_spans(text: &TextRef, rmatches: &[WordMatch]) -> Vec<HighlightSpan> {
+    let Text { words, source, .. } = text;
+    let mut spans = Vec::with_capacity(rmatches.len());
+    for (word_offset, word) in words.iter().enumerate() {
+        if let Some(rmatch) = rmatches.iter().find(|m| m.offset == word_offset) {
+            let match_start = word.slice.0 + rmatch.subslice.0;
+            let match_end   = word.slice.0 + rmatch.subslice.1;
+            if match_start == match_end { continue; }
+            let (chars0, bytes0, utf16_0) = source_offsets(&source[.. match_start]);
+            let (chars1, bytes1, utf16_1) = source_offsets(&source[match_start .. match_end]);
+            spans.push(HighlightSpan {
+                chars: (chars0, chars0 + chars1),
+                bytes: (bytes0, bytes0 + bytes1),
+                utf16: (utf16_0, utf16_0 + utf16_1),
+            });
+        }
+    }
+    spans
+}
+
+
+/// Source chars are padded with \0 where normalization expanded them, these don't count.
+fn source_offsets(source: &[char]) -> (usize, usize, usize) {
+    source
+        .iter()
+        .filter(|&&ch| ch != '\0')
+        .fold((0, 0, 0), |(chars, bytes, utf16), ch| {
+            (chars + 1, bytes + ch.len_utf8(), utf16 + ch.len_utf16())
+        })
+}
+
+
 #[cfg(test)]
 mod tests {
     use crate::matching::WordMatch;
     use crate::store::Record;
     use crate::search::Hit;
     use crate::lang::{Lang, lang_german, lang_portuguese};
-    use super::highlight;
+    use super::{highlight, highlight_fields, highlight_spans, HighlightSpan};
 
     const L: &[char] = &['['];
     const R: &[char] = &[']'];
@@ -154,4 +218,42 @@ mod tests {
 
         assert_eq!(&received, expected);
     }
+
+    #[test]
+    fn highlight_spans_utf() {
+        let lang   = lang_german();
+        let record = Record::new(10, "Straße 𝄞 Passstraße", 0, &lang);
+
+        let mut hit = Hit::from_record(&record);
+        let (rmatch, _) = mock_match(1, 9);
+        hit.rmatches.push(rmatch);
+
+        let expected = vec![HighlightSpan {
+            chars: (9, 18),
+            bytes: (13, 23),
+            utf16: (10, 19),
+        }];
+        let received = highlight_spans(&hit);
+
+        assert_eq!(received, expected);
+        assert_eq!(&highlight(&hit, (L, R)), "Straße 𝄞 [Passstraß]e");
+    }
+
+    #[test]
+    fn highlight_fields_basic() {
+        let lang       = Lang::new();
+        let mut record = Record::new(10, "metal detector", 0, &lang);
+        record.add_field("Garrett", &lang);
+        record.add_field("Outdoor", &lang);
+
+        let mut hit = Hit::from_record(&record);
+        let (rmatch, _) = mock_match(0, 4);
+        hit.fields[1].rmatches.push(rmatch);
+
+        let expected = vec!["Garrett".to_string(), "[Outd]oor".to_string()];
+        let received = highlight_fields(&hit, (L, R));
+
+        assert_eq!(received, expected);
+        assert_eq!(&highlight(&hit, (L, R)), "metal detector");
+    }
 }
@@ -1,30 +1,64 @@
 use std::default::Default;
 use crate::tokenization::TextRef;
 use crate::matching::WordMatch;
-use crate::store::Record;
+use crate::store::{Record, Store, DEFAULT_FIELD_WEIGHT};
 use super::score::Scores;
 
 
 #[derive(Debug)]
 pub struct Hit<'a> {
+    pub ix:      usize,
     pub id:      usize,
     pub title:   TextRef<'a>,
+    pub fields:  Vec<FieldHit<'a>>,
     pub rating:  usize,
+    pub lang:    usize,
     pub rmatches: Vec<WordMatch>,
     pub qmatches: Vec<WordMatch>,
     pub scores:  Scores,
 }
 
 
+#[derive(Debug)]
+pub struct FieldHit<'a> {
+    pub text:     TextRef<'a>,
+    pub weight:   f64,
+    pub rmatches: Vec<WordMatch>,
+    pub qmatches: Vec<WordMatch>,
+}
+
+
 impl<'a> Hit<'a> {
     pub fn from_record(record: &'a Record) -> Hit<'a> {
         Hit {
+            ix:       record.ix,
             id:       record.id,
             title:    record.title.to_ref(),
+            fields:   record.fields.iter().map(|f| FieldHit::new(f.to_ref())).collect(),
             rating:   record.rating,
+            lang:     record.lang,
             scores:   Default::default(),
             rmatches: Vec::new(),
             qmatches: Vec::new(),
         }
     }
+
+    pub fn with_weights(mut self, store: &Store) -> Self {
+        for (ix, field) in self.fields.iter_mut().enumerate() {
+            field.weight = store.field_weight(ix);
+        }
+        self
+    }
+}
+
+
+impl<'a> FieldHit<'a> {
+    pub fn new(text: TextRef<'a>) -> Self {
+        Self {
+            text,
+            weight:   DEFAULT_FIELD_WEIGHT,
+            rmatches: Vec::new(),
+            qmatches: Vec::new(),
+        }
+    }
 }
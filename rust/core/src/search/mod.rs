@@ -2,72 +2,365 @@ mod hit;
 mod score;
 mod result;
 mod filter;
+mod attr_filter;
+mod facet;
 mod sort;
+mod ranking;
+mod explain;
 mod highlight;
+mod session;
+mod cache;
 
+use std::slice;
+use std::sync::PoisonError;
 use crate::utils::LimitSort;
-use crate::tokenization::TextRef;
-use crate::store::Store;
+use crate::tokenization::{TextRef, Synonym, tokenize_synonyms};
+use crate::store::{Store, TrigramIndex, Candidates};
+use crate::matching::MatchBuffers;
+use crate::lang::{KeyboardLayout, layout_variants};
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use rayon::prelude::*;
 pub use hit::Hit;
 pub use result::SearchResult;
+pub use attr_filter::{Filter, FilterError};
+pub use facet::FacetCount;
+pub use score::ScoreType;
+pub use ranking::RankingConfig;
+pub use explain::{Explanation, FieldExplanation, MatchExplanation};
+pub use highlight::HighlightSpan;
+pub use session::SearchSession;
+pub use cache::ResultCache;
+
+
+/// Fewest candidates scored by one thread with the `parallel` feature.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const SCORE_CHUNK: usize = 64;
+
+/// Fewest hits the candidate pool is sized for on pages past the first.
+const POOL_SIZE: usize = 100;
 
 
 impl Store {
     pub fn search<'a>(
         &'a self,
         query: &'a TextRef<'a>,
+        filter: Option<&Filter>,
     ) -> Vec<SearchResult> {
+        self.search_faceted(query, filter).0
+    }
+
+    /// Also counts values of `self.facets` attributes over all matching hits, not only the top ones.
+    pub fn search_faceted<'a>(
+        &'a self,
+        query: &'a TextRef<'a>,
+        filter: Option<&Filter>,
+    ) -> (Vec<SearchResult>, Vec<FacetCount>) {
+        self.search_page(slice::from_ref(query), filter, 0, self.limit)
+    }
+
+    /// Ranked hits from `offset` to `offset + limit`, ties are broken by insertion order.
+    /// Candidates are the top `10 * (offset + limit)` records by trigram count for the first page,
+    /// pages past it take at least `10 * POOL_SIZE`, so those ending within `POOL_SIZE` hits are cut from the same ranking,
+    /// while the first one may differ from them and deeper ones widen the pool and may reorder hits.
+    /// `queries` hold the query tokenized under each store language, see `Store::tokenize_query`,
+    /// every record is matched with the one of its own language, or the first one if missing.
+    pub fn search_page<'a>(
+        &'a self,
+        queries: &'a [TextRef<'a>],
+        filter:  Option<&Filter>,
+        offset:  usize,
+        limit:   usize,
+    ) -> (Vec<SearchResult>, Vec<FacetCount>) {
+        let mut counts = self.take_counts();
+        let (results, facets, _) = self.search_within(queries, filter, offset, limit, None, &mut counts);
+        self.give_counts(counts);
+        (results, facets)
+    }
+
+    /// Same as `search_page`, counting trigrams faster with `within` candidates of an earlier query.
+    /// Also returns the number of hits on all pages, as far as the candidate pool goes.
+    /// Trigram counts of the query are left in `counts`, empty for an empty query.
+    fn search_within<'a>(
+        &'a self,
+        queries: &'a [TextRef<'a>],
+        filter:  Option<&Filter>,
+        offset:  usize,
+        limit:   usize,
+        within:  Option<&Candidates>,
+        counts:  &mut Vec<usize>,
+    ) -> (Vec<SearchResult>, Vec<FacetCount>, usize) {
         let dividers = self.dividers();
+        let query    = &queries[0];
+        let synonyms = self.query_synonyms(queries);
+        let query_of = |hit: &Hit| queries.get(hit.lang).unwrap_or(query);
+        let synonyms_of = |hit: &Hit| synonyms.get(hit.lang).unwrap_or(&synonyms[0]);
+        let size     = offset.saturating_add(limit);
+        let pool     = if offset == 0 { size } else { max!(size, POOL_SIZE) };
+        let accept   = |ix: usize| {
+            match filter {
+                Some(filter) => filter.matches(&self.records[ix]),
+                None         => true,
+            }
+        };
 
-        let ixs = if query.words.len() > 0 {
-            self.index.borrow_mut().prepare(&query, self.limit)
+        let grams = if !query.words.is_empty() {
+            Self::query_grams(queries, &synonyms)
         } else {
-            self.top_ixs()
+            Vec::new()
         };
 
-        ixs.iter()
-            .map(|&ix| {
-                Hit::from_record(&self.records[ix])
-            })
-            .map(|mut hit| {
-                score::score(query, &mut hit);
-                hit
-            })
-            .filter(|hit| {
-                filter::hit_matches(query, hit)
-            })
-            .limit_sort_unstable(self.limit, sort::compare_hits)
+        let ixs = if !query.words.is_empty() {
+            self.index.prepare(&grams, &self.fields, pool, accept, within, counts)
+        } else if filter.is_some() || self.ranking != RankingConfig::default() {
+            self.rank_ixs(size, accept)
+        } else {
+            self.top_ixs(size)
+        };
+
+        let score_hit = |buffers: &mut MatchBuffers, ix: usize| {
+            let mut hit = Hit::from_record(&self.records[ix]).with_weights(self);
+            score::score(query_of(&hit), synonyms_of(&hit), self.lang_at(hit.lang), &self.matching, buffers, &mut hit);
+            if filter::hit_matches(query_of(&hit), &hit) { Some(hit) } else { None }
+        };
+
+        #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+        let hits = {
+            let mut buffers = MatchBuffers::new();
+            ixs.iter()
+                .filter_map(|&ix| score_hit(&mut buffers, ix))
+                .collect::<Vec<_>>()
+        };
+
+        // Every thread gets its own buffers, hits keep the order of candidates.
+        #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+        let hits = ixs.par_iter()
+            .with_min_len(SCORE_CHUNK)
+            .map_init(MatchBuffers::new, |buffers, &ix| score_hit(buffers, ix))
+            .flatten()
+            .collect::<Vec<_>>();
+
+        let facets = if self.facets.is_empty() {
+            Vec::new()
+        } else if query.is_empty() {
+            // Every record is a hit, but only the top ones were prepared.
+            facet::count_facets(&self.facets, self.records.iter().filter(|r| accept(r.ix)))
+        } else {
+            // Records sharing a trigram with the query past the candidate pool are scored just for facets.
+            let mut pooled = ixs.clone();
+            pooled.sort_unstable();
+            let mut buffers = MatchBuffers::new();
+            let rest = counts
+                .iter()
+                .enumerate()
+                .filter(|&(ix, &count)| count > 0 && pooled.binary_search(&ix).is_err() && accept(ix))
+                .filter_map(|(ix, _)| score_hit(&mut buffers, ix))
+                .collect::<Vec<_>>();
+            facet::count_facets(&self.facets, hits.iter().chain(rest.iter()).map(|hit| &self.records[hit.ix]))
+        };
+
+        let candidates = ixs.len();
+        let found      = hits.len();
+        let results    = hits
+            .into_iter()
+            .limit_sort_unstable(size, |h1, h2| sort::compare_hits(h1, h2, &self.ranking))
+            .skip(offset)
             .map(|hit| {
+                let explanation = if self.explain {
+                    let found = self.index.record_grams(&grams, hit.ix);
+                    Some(explain::explain(query_of(&hit), &hit, &self.fields, found, candidates))
+                } else {
+                    None
+                };
+                // Records added before a field have none, but results keep one entry per store field.
+                let mut fields      = highlight::highlight_fields(&hit, dividers);
+                let mut field_spans = highlight::highlight_field_spans(&hit);
+                fields.resize(self.fields.len(), String::new());
+                field_spans.resize(self.fields.len(), Vec::new());
                 SearchResult {
-                    id:    hit.id,
-                    title: highlight::highlight(&hit, dividers),
+                    id:          hit.id,
+                    title:       highlight::highlight(&hit, dividers),
+                    fields,
+                    spans:       highlight::highlight_spans(&hit),
+                    field_spans,
+                    explain:     explanation,
+                    correction:  None,
                 }
             })
+            .collect();
+
+        (results, facets, found)
+    }
+
+    /// Synonyms found in the query of every store language.
+    fn query_synonyms(&self, queries: &[TextRef]) -> Vec<Vec<Synonym>> {
+        queries
+            .iter()
+            .enumerate()
+            .map(|(lang_ix, query)| tokenize_synonyms(query, self.lang_at(lang_ix)))
             .collect()
     }
 
-    fn top_ixs(&self) -> Vec<usize> {
-        let top_ixs = &mut *self.top_ixs.borrow_mut();
+    /// Trigrams of the first query, of the others and of synonyms too, see `TrigramIndex::query_grams`.
+    fn query_grams(queries: &[TextRef], synonyms: &[Vec<Synonym>]) -> Vec<[char; 3]> {
+        let extra = queries[1 ..]
+            .iter()
+            .cloned()
+            .chain(synonyms.iter().flatten().map(|s| s.text.to_ref()))
+            .collect::<Vec<_>>();
+        TrigramIndex::query_grams(&queries[0], &extra)
+    }
+
+    /// Tokenizes the query under store languages and searches a page.
+    /// When fewer than `self.layout_retry` hits are found, retries the query retyped with other keyboard layouts
+    /// of store languages, e.g. "rjdhbr" for "коврик", and appends new hits marked with the `correction`.
+    /// Facets are counted for the original query, or for the first correction if the original found nothing.
+    /// Pages of queries without a filter are kept in `self.cache` if it has capacity.
+    pub fn search_text(
+        &self,
+        query:  &str,
+        filter: Option<&Filter>,
+        offset: usize,
+        limit:  usize,
+    ) -> (Vec<SearchResult>, Vec<FacetCount>) {
+        if let Some(found) = self.cached(query, filter, offset, limit) {
+            return found;
+        }
+        let found = self.search_layouts(query, filter, offset, limit);
+        self.cache_page(query, filter, offset, limit, &found);
+        found
+    }
+
+    // The cache is not locked while searching, so concurrent searches don't wait for each other.
+    fn cached(&self, query: &str, filter: Option<&Filter>, offset: usize, limit: usize) -> Option<(Vec<SearchResult>, Vec<FacetCount>)> {
+        if filter.is_some() {
+            return None;
+        }
+        self.cache.lock().unwrap_or_else(PoisonError::into_inner).get(self.version, query, offset, limit)
+    }
+
+    // Corrections keep the case of the query, which the cache key doesn't, so pages with them aren't cached.
+    fn cache_page(&self, query: &str, filter: Option<&Filter>, offset: usize, limit: usize, found: &(Vec<SearchResult>, Vec<FacetCount>)) {
+        if filter.is_none() && found.0.iter().all(|result| result.correction.is_none()) {
+            self.cache.lock().unwrap_or_else(PoisonError::into_inner).put(self.version, query, offset, limit, found);
+        }
+    }
+
+    fn search_layouts(
+        &self,
+        query:  &str,
+        filter: Option<&Filter>,
+        offset: usize,
+        limit:  usize,
+    ) -> (Vec<SearchResult>, Vec<FacetCount>) {
+        if self.layout_retry == 0 || query.trim().is_empty() {
+            let (results, facets, _) = self.search_query(query, filter, offset, limit);
+            return (results, facets);
+        }
+        // Hits past the page count too, so small pages don't retry queries finding plenty.
+        let found = self.search_query(query, filter, 0, offset.saturating_add(limit));
+        self.retry_layouts(query, filter, offset, limit, found)
+    }
+
+    fn search_query(
+        &self,
+        query:  &str,
+        filter: Option<&Filter>,
+        offset: usize,
+        limit:  usize,
+    ) -> (Vec<SearchResult>, Vec<FacetCount>, usize) {
+        let queries    = self.tokenize_query(query);
+        let queries    = queries.iter().map(|q| q.to_ref()).collect::<Vec<_>>();
+        let mut counts = self.take_counts();
+        let found      = self.search_within(&queries, filter, offset, limit, None, &mut counts);
+        self.give_counts(counts);
+        found
+    }
+
+    /// Appends hits of layout variants of the query to the `found` hits from 0 to `offset + limit`
+    /// if there are fewer than `self.layout_retry` of them, and cuts the page.
+    fn retry_layouts(
+        &self,
+        query:  &str,
+        filter: Option<&Filter>,
+        offset: usize,
+        limit:  usize,
+        found:  (Vec<SearchResult>, Vec<FacetCount>, usize),
+    ) -> (Vec<SearchResult>, Vec<FacetCount>) {
+        let size = offset.saturating_add(limit);
+        let (mut results, mut facets, found) = found;
+        if found >= self.layout_retry {
+            results.drain(.. min!(offset, results.len()));
+            return (results, facets);
+        }
+
+        let mut layouts = vec![KeyboardLayout::Qwerty];
+        for lang in self.all_langs() {
+            if !layouts.contains(&lang.keyboard()) {
+                layouts.push(lang.keyboard());
+            }
+        }
+        for variant in layout_variants(query, &layouts) {
+            if results.len() >= size { break; }
+            let (corrected, corrected_facets, _) = self.search_query(&variant, filter, 0, size);
+            if results.is_empty() && !corrected.is_empty() {
+                facets = corrected_facets;
+            }
+            for mut result in corrected {
+                if results.len() >= size { break; }
+                if results.iter().any(|r| r.id == result.id) { continue; }
+                result.correction = Some(variant.clone());
+                results.push(result);
+            }
+        }
+
+        results.drain(.. min!(offset, results.len()));
+        (results, facets)
+    }
+
+    fn top_ixs(&self, size: usize) -> Vec<usize> {
+        let top_ixs = &mut *self.top_ixs.lock().unwrap_or_else(PoisonError::into_inner);
 
         if let Some(ixs) = top_ixs {
-            return ixs.clone();
+            if ixs.len() >= size || ixs.len() == self.records.len() {
+                return ixs[.. min!(size, ixs.len())].to_vec();
+            }
         }
 
-        let ixs = self.records
+        let ixs = self.rank_ixs(size, |_| true);
+        *top_ixs = Some(ixs.clone());
+        ixs
+    }
+
+    /// Top records for an empty query, a custom ranking orders them by the scores only records differ in.
+    fn rank_ixs(&self, size: usize, accept: impl Fn(usize) -> bool) -> Vec<usize> {
+        if self.ranking != RankingConfig::default() {
+            return self.records
+                .iter()
+                .filter(|r| accept(r.ix))
+                .map(|r| (r.ix, score::record_scores(r)))
+                .limit_sort_unstable(
+                    size,
+                    |(ix1, scores1), (ix2, scores2)| {
+                        sort::compare_scores(scores1, scores2, &self.ranking).then_with(|| ix1.cmp(ix2))
+                    },
+                )
+                .map(|(ix, _)| ix)
+                .collect();
+        }
+        self.records
             .iter()
+            .filter(|r| accept(r.ix))
             .limit_sort_unstable(
-                self.limit,
+                size,
                 |r1, r2| {
                     r2.rating
                         .cmp(&r1.rating)
                         .then_with(|| r1.title.chars.cmp(&r2.title.chars))
+                        .then_with(|| r1.ix.cmp(&r2.ix))
                 },
             )
             .map(|r| r.ix)
-            .collect::<Vec<_>>();
-
-        *top_ixs = Some(ixs.clone());
-        ixs
+            .collect()
     }
 }
 
@@ -76,8 +369,10 @@ impl Store {
 mod tests {
     use insta::assert_debug_snapshot;
     use crate::tokenization::tokenize_query;
-    use crate::lang::{Lang, lang_english, lang_german};
-    use crate::store::{Store, Record};
+    use crate::lang::{Lang, Phonetic, lang_english, lang_german, lang_russian, CYRILLIC_TO_LATIN};
+    use crate::store::{Store, Record, Attribute};
+    use crate::matching::MatchConfig;
+    use super::{Filter, ScoreType, RankingConfig};
 
     fn check(name: &str, lang: Lang, queries: &[&str]) {
         let mut store = Store::new();
@@ -91,7 +386,7 @@ mod tests {
         for (i, query) in queries.iter().enumerate() {
             let query   = tokenize_query(query, &store.lang);
             let query   = query.to_ref();
-            let results = store.search(&query);
+            let results = store.search(&query, None);
             assert_debug_snapshot!(format!("{}-{}", name, i), results);
         }
     }
@@ -109,7 +404,7 @@ mod tests {
         store.add(Record::new(30, "yellow metal mailbox", 10, &store.lang));
         store.add(Record::new(40, "thesaurus",            10, &store.lang));
         store.add(Record::new(50, "wi-fi router",         10, &store.lang));
-        assert_debug_snapshot!(store.top_ixs());
+        assert_debug_snapshot!(store.top_ixs(store.limit));
     }
 
     #[test]
@@ -151,6 +446,107 @@ mod tests {
     }
 
 
+    #[test]
+    fn search_layout_retry() {
+        let mut store = Store::new();
+        store.lang = lang_english();
+        let ru = store.add_lang(lang_russian());
+        let mut record = Record::new(10, "Коврик для мыши", 10, store.get_lang(ru).unwrap());
+        record.lang = ru;
+        store.add(record);
+        store.add(Record::new(20, "Mouse pad", 20, &store.lang));
+        let search = |store: &Store, query: &str| {
+            let (results, _) = store.search_text(query, None, 0, 10);
+            results.into_iter().map(|r| (r.title, r.correction)).collect::<Vec<_>>()
+        };
+        assert_eq!(search(&store, "rjdhbr"), vec![]);
+        store.layout_retry = 1;
+        assert_eq!(search(&store, "rjdhbr"), vec![("[Коврик] для мыши".to_string(), Some("коврик".to_string()))]);
+        assert_eq!(search(&store, "ьщгыу"), vec![("[Mouse] pad".to_string(), Some("mouse".to_string()))]);
+        assert_eq!(search(&store, "mouse"), vec![("[Mouse] pad".to_string(), None)]);
+        store.cache.get_mut().unwrap().set_capacity(10);
+        search(&store, "RJDHBR");
+        assert_eq!(search(&store, "rjdhbr"), vec![("[Коврик] для мыши".to_string(), Some("коврик".to_string()))]);
+        assert_eq!(store.cache.get_mut().unwrap().hits, 0);
+    }
+
+    #[test]
+    fn search_transliteration() {
+        let mut store = Store::new();
+        store.lang = lang_russian();
+        for (from, to) in CYRILLIC_TO_LATIN {
+            store.lang.add_transliteration(from, to);
+        }
+        store.add(Record::new(10, "Samsung Galaxy", 10, &store.lang));
+        store.add(Record::new(20, "Чехол Самсунг", 20, &store.lang));
+        let search = |store: &Store, query: &str| {
+            let (results, _) = store.search_text(query, None, 0, 10);
+            results.into_iter().map(|r| r.title).collect::<Vec<_>>()
+        };
+        assert_eq!(search(&store, "самсунг"), vec!["[Samsung] Galaxy", "Чехол [Самсунг]"]);
+        assert_eq!(search(&store, "samsung"), vec!["[Samsung] Galaxy", "Чехол [Самсунг]"]);
+        assert_eq!(search(&store, "чехол"),   vec!["[Чехол] Самсунг"]);
+        assert_eq!(search(&store, "чех"),     vec!["[Чех]ол Самсунг"]);
+    }
+
+    #[test]
+    fn search_phonetic() {
+        let mut store = Store::new();
+        store.lang = lang_english();
+        store.lang.set_phonetic(Some(Phonetic::DoubleMetaphone));
+        store.add(Record::new(10, "John Smith", 10, &store.lang));
+        store.add(Record::new(20, "Jon Smyth",  20, &store.lang));
+        store.add(Record::new(30, "Jack Smart", 30, &store.lang));
+        let search = |query: &str| {
+            let (results, _) = store.search_text(query, None, 0, 10);
+            results.into_iter().map(|r| r.title).collect::<Vec<_>>()
+        };
+        assert_eq!(search("jon smyth"),  vec!["[Jon] [Smyth]", "[John] [Smith]"]);
+        assert_eq!(search("john smith"), vec!["[John] [Smith]", "[Jon] [Smyth]"]);
+    }
+
+    #[test]
+    fn search_match_config() {
+        let mut store = Store::new();
+        store.add(Record::new(10, "Cable AB-1200", 10, &store.lang));
+        store.add(Record::new(20, "Cable AB-1300", 20, &store.lang));
+        let search = |store: &Store, query: &str| {
+            let (results, _) = store.search_text(query, None, 0, 10);
+            results.into_iter().map(|r| r.title).collect::<Vec<_>>()
+        };
+        assert_eq!(search(&store, "cable 1200"), vec!["[Cable] AB-[1200]", "[Cable] AB-[1300]"]);
+        store.matching.typos = false;
+        assert_eq!(search(&store, "cable 1200"), vec!["[Cable] AB-[1200]", "[Cable] AB-1300"]);
+        assert_eq!(search(&store, "cab ab 12"),  vec!["Cable [AB]-[12]00", "Cable [AB]-1300"]);
+        store.matching.prefix_only = true;
+        assert_eq!(search(&store, "cab ab 12"),  vec!["[Cab]le [AB]-[12]00", "[Cab]le [AB]-1300"]);
+        store.matching = MatchConfig { damlev_threshold: 0.0, ..MatchConfig::default() };
+        assert_eq!(search(&store, "cable 1200"), vec!["[Cable] AB-[1200]", "[Cable] AB-1300"]);
+    }
+
+    #[test]
+    fn search_concurrent() {
+        fn assert_sync<T: Send + Sync>(_: &T) { }
+        let mut store = Store::new();
+        store.lang = lang_english();
+        store.add(Record::new(10, "Yellow metal mailbox", 10, &store.lang));
+        store.add(Record::new(20, "Brown plush bear",     20, &store.lang));
+        assert_sync(&store);
+        let store   = std::sync::Arc::new(store);
+        let threads = ["mailbox", "bear", "", "metl"]
+            .iter()
+            .map(|&query| {
+                let store = store.clone();
+                std::thread::spawn(move || {
+                    let (results, _) = store.search_text(query, None, 0, 10);
+                    results.into_iter().map(|r| r.id).collect::<Vec<_>>()
+                })
+            })
+            .collect::<Vec<_>>();
+        let ids = threads.into_iter().map(|t| t.join().unwrap()).collect::<Vec<_>>();
+        assert_eq!(ids, vec![vec![10], vec![20], vec![20, 10], vec![10]]);
+    }
+
     #[test]
     fn search_stemming() {
         let empty_lang = Lang::new();
@@ -162,13 +558,48 @@ mod tests {
         let query2   = tokenize_query("university", &store.lang);
         let query1   = query1.to_ref();
         let query2   = query2.to_ref();
-        let results1 = store.search(&query1);
-        let results2 = store.search(&query2);
+        let results1 = store.search(&query1, None);
+        let results2 = store.search(&query2, None);
 
         assert_debug_snapshot!(results1);
         assert_debug_snapshot!(results2);
     }
 
+    #[test]
+    fn search_record_langs() {
+        let mut store = Store::new();
+        let en = store.add_lang(lang_english());
+        let mut record = Record::new(30, "universe", 30, store.get_lang(en).unwrap());
+        record.lang = en;
+        store.add(record);
+
+        let queries = store.tokenize_query("university");
+        let queries = queries.iter().map(|q| q.to_ref()).collect::<Vec<_>>();
+        let search  = |queries| {
+            let (results, _) = store.search_page(queries, None, 0, 10);
+            results.into_iter().map(|r| r.title).collect::<Vec<_>>()
+        };
+        assert_eq!(search(&queries[.. 1]), Vec::<String>::new());
+        assert_eq!(search(&queries[..]), vec!["[univers]e"]);
+    }
+
+    #[test]
+    fn search_detected_synonyms() {
+        let mut store = Store::new();
+        store.lang   = lang_english();
+        store.detect = true;
+        let mut ru = lang_russian();
+        ru.add_synonym("щётка", "ёршик");
+        let ru = store.add_lang(ru);
+        let mut record = Record::new(10, "Ёршик для бутылок", 10, store.get_lang(ru).unwrap());
+        record.lang = ru;
+        store.add(record);
+
+        let (results, _) = store.search_text("щётка", None, 0, 10);
+        let titles = results.into_iter().map(|r| r.title).collect::<Vec<_>>();
+        assert_eq!(titles, vec!["[Ёршик] для бутылок"]);
+    }
+
     #[test]
     fn search_particles() {
         check("particles_nolang", Lang::new(), &[
@@ -192,6 +623,228 @@ mod tests {
     }
 
 
+    #[test]
+    fn search_fields() {
+        let mut store = Store::new();
+        store.add_field("brand", 0.5);
+        let mut record1 = Record::new(10, "running shoes", 10, &store.lang);
+        let mut record2 = Record::new(20, "nike sweatshirt", 20, &store.lang);
+        let record3     = Record::new(30, "tennis shoes", 30, &store.lang);
+        record1.add_field("Nike", &store.lang);
+        record2.add_field("Puma", &store.lang);
+        store.add(record1);
+        store.add(record2);
+        store.add(record3);
+
+        let query   = tokenize_query("nike shoes", &store.lang);
+        let query   = query.to_ref();
+        let results = store.search(&query, None);
+        assert_debug_snapshot!(results);
+    }
+
+
+    #[test]
+    fn search_attr_filter() {
+        let mut store = Store::new();
+        store.limit = 2;
+        for id in 1 ..= 6 {
+            let mut record = Record::new(id, "metal mailbox", id, &store.lang);
+            record.set_attr("in_stock", Attribute::Int((id % 3 == 0) as i64));
+            store.add(record);
+        }
+        let filter = Filter::parse("in_stock = 1").unwrap();
+        let search = |query: &str| {
+            let query = tokenize_query(query, &store.lang);
+            store.search(&query.to_ref(), Some(&filter))
+                .iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search("mailbox"), vec![6, 3]);
+        assert_eq!(search(""), vec![6, 3]);
+    }
+
+
+    #[test]
+    fn search_facets() {
+        let mut store = Store::new();
+        store.limit  = 1;
+        store.facets = vec!["color".to_string()];
+        for (id, title, color) in &[
+            (10, "red mailbox",    "red"),
+            (20, "blue mailbox",   "blue"),
+            (30, "red metal box",  "red"),
+            (40, "brown bear",     "brown"),
+        ] {
+            let mut record = Record::new(*id, title, *id, &store.lang);
+            record.set_attr("color", Attribute::keywords(&[color]));
+            store.add(record);
+        }
+        let counts = |query: &str| {
+            let query = tokenize_query(query, &store.lang);
+            let (results, facets) = store.search_faceted(&query.to_ref(), None);
+            assert_eq!(results.len(), 1);
+            facets
+                .into_iter()
+                .map(|f| (f.value, f.count))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(counts("mailbox"), vec![("blue".to_string(), 1), ("red".to_string(), 1)]);
+        assert_eq!(counts(""), vec![
+            ("red".to_string(),   2),
+            ("blue".to_string(),  1),
+            ("brown".to_string(), 1),
+        ]);
+    }
+
+
+    #[test]
+    fn search_ranking() {
+        let mut store = Store::new();
+        store.add(Record::new(10, "metal mailbox", 10, &store.lang));
+        store.add(Record::new(20, "yellow metal mailbox", 20, &store.lang));
+        store.add(Record::new(30, "mailbox of metal", 30, &store.lang));
+        let search = |store: &Store| {
+            let query = tokenize_query("metal mailbox", &store.lang);
+            store.search(&query.to_ref(), None)
+                .iter()
+                .map(|r| r.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search(&store), vec![10, 20, 30]);
+
+        store.ranking.order = vec![ScoreType::Chars, ScoreType::Rating];
+        assert_eq!(search(&store), vec![30, 20, 10]);
+
+        store.ranking.order   = RankingConfig::default().order;
+        store.ranking.weights = vec![(ScoreType::Trans, 100.0), (ScoreType::Rating, 1.0)];
+        assert_eq!(search(&store), vec![20, 10, 30]);
+
+        store.limit   = 1;
+        store.ranking = RankingConfig { order: vec![ScoreType::WordLen], weights: Vec::new() };
+        let query     = tokenize_query("", &store.lang);
+        let ids       = store.search(&query.to_ref(), None).iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(ids, vec![10]);
+        let (results, _) = store.search_text("", None, 1, 1);
+        assert_eq!(results.iter().map(|r| r.id).collect::<Vec<_>>(), vec![20]);
+    }
+
+    #[test]
+    fn search_explain() {
+        let mut store = Store::new();
+        store.explain = true;
+        store.add_field("brand", 2.0);
+        let mut record = Record::new(10, "yellow metal mailbox", 10, &store.lang);
+        record.add_field("Acme", &store.lang);
+        store.add(record);
+        let mut record = Record::new(20, "brown plush bear", 20, &store.lang);
+        record.add_field("", &store.lang);
+        store.add(record);
+
+        let query   = tokenize_query("metl mail acme", &store.lang);
+        let query   = query.to_ref();
+        let results = store.search(&query, None);
+        assert_debug_snapshot!(results);
+    }
+
+    #[test]
+    fn search_pages() {
+        let mut store = Store::new();
+        for id in 1 ..= 25 {
+            let title = if id % 2 == 0 { "metal mailbox" } else { "mailbox of metal" };
+            store.add(Record::new(id, title, id, &store.lang));
+        }
+        let page = |query: &str, offset: usize| {
+            let query = tokenize_query(query, &store.lang);
+            let (results, _) = store.search_page(&[query.to_ref()], None, offset, 10);
+            results.iter().map(|r| r.id).collect::<Vec<_>>()
+        };
+        for query in &["mailbox", ""] {
+            let mut ids = [page(query, 0), page(query, 10), page(query, 20)].concat();
+            assert_eq!(ids.len(), 25);
+            assert_eq!(ids[.. 10], page(query, 0)[..]);
+            ids.sort();
+            ids.dedup();
+            assert_eq!(ids, (1 ..= 25).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn search_reuses_counts() {
+        let mut store = Store::new();
+        store.add(Record::new(10, "metal mailbox", 10, &store.lang));
+        store.search_text("mailbox", None, 0, 10);
+        store.search_text("metal", None, 0, 10);
+        let counts = store.counts.lock().unwrap();
+        assert_eq!(counts.len(), 1);
+        assert_eq!(counts[0].len(), store.records.len());
+    }
+
+    #[test]
+    fn search_pages_pool() {
+        let mut store = Store::new();
+        store.ranking = RankingConfig { order: vec![ScoreType::Rating], weights: Vec::new() };
+        for id in 0 .. 1_500 {
+            let title = if id % 2 == 0 { "metal mailbox" } else { "mailbox" };
+            store.add(Record::new(id, title, id, &store.lang));
+        }
+        store.explain = true;
+        let query = tokenize_query("metal mailbox", &store.lang);
+        let page  = |offset: usize, limit: usize| {
+            let (results, _) = store.search_page(&[query.to_ref()], None, offset, limit);
+            let candidates   = results[0].explain.as_ref().unwrap().candidates;
+            (results.iter().map(|r| r.id).collect::<Vec<_>>(), candidates)
+        };
+        assert_eq!(page(0, 10).1, 100);
+        assert_eq!(page(10, 10).1, 1_000);
+        assert_eq!(page(100, 20).1, 1_200);
+        let pages = (1 .. 5).map(|n| page(n * 20, 20).0).collect::<Vec<_>>().concat();
+        assert_eq!(pages, page(20, 80).0);
+    }
+
+    #[test]
+    fn search_facets_past_pool() {
+        let mut store = Store::new();
+        store.limit  = 1;
+        store.facets = vec!["color".to_string()];
+        for id in 0 .. 2_000 {
+            let title      = if id % 4 == 0 { "brown bear" } else { "metal mailbox" };
+            let color      = if id % 2 == 0 { "red" } else { "blue" };
+            let mut record = Record::new(id, title, 0, &store.lang);
+            record.set_attr("color", Attribute::keywords(&[color]));
+            store.add(record);
+        }
+        let query = tokenize_query("mailbox", &store.lang);
+        let (_, facets) = store.search_faceted(&query.to_ref(), None);
+        let facets = facets
+            .into_iter()
+            .map(|f| (f.value, f.count))
+            .collect::<Vec<_>>();
+        assert_eq!(facets, vec![("blue".to_string(), 1_000), ("red".to_string(), 500)]);
+    }
+
+    #[test]
+    fn search_synonyms() {
+        let mut store = Store::new();
+        store.lang.add_synonym("tv", "television");
+        store.lang.add_synonyms(&["hoodie", "hooded sweatshirt"]);
+        store.add(Record::new(10, "Smart Television 55in", 10, &store.lang));
+        store.add(Record::new(20, "Warm hooded sweatshirt", 20, &store.lang));
+        store.add(Record::new(30, "Zip hoodie",             30, &store.lang));
+        let search = |query: &str| {
+            let query = tokenize_query(query, &store.lang);
+            store.search(&query.to_ref(), None)
+                .into_iter()
+                .map(|r| r.title)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(search("tv"), vec!["Smart [Television] 55in"]);
+        assert_eq!(search("television"), vec!["Smart [Television] 55in"]);
+        assert_eq!(search("hoodie"), vec!["Warm [hooded] [sweatshirt]", "Zip [hoodie]"]);
+        assert_eq!(search("hooded sweatshirt"), vec!["Warm [hooded] [sweatshirt]", "Zip [hoodie]"]);
+    }
+
+
     #[test]
     fn search_utf_normalization() {
         let mut store = Store::new();
@@ -209,7 +862,7 @@ mod tests {
         for query in &queries {
             let query  = tokenize_query(query, &store.lang);
             let query  = query.to_ref();
-            let result = store.search(&query);
+            let result = store.search(&query, None);
             assert_debug_snapshot!(result);
         }
     }
@@ -0,0 +1,31 @@
+use super::score::{Scores, ScoreType};
+
+
+/// How hits are ordered by their scores.
+/// Hits are compared by the weighted sum of `weights` first, if any,
+/// then one by one by the components in `order`. Components left out are ignored.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RankingConfig {
+    pub order:   Vec<ScoreType>,
+    pub weights: Vec<(ScoreType, f64)>,
+}
+
+
+impl RankingConfig {
+    pub fn weighted_sum(&self, scores: &Scores) -> f64 {
+        self.weights
+            .iter()
+            .map(|&(score, weight)| scores[score] as f64 * weight)
+            .sum()
+    }
+}
+
+
+impl Default for RankingConfig {
+    fn default() -> Self {
+        Self {
+            order:   ScoreType::ALL.to_vec(),
+            weights: Vec::new(),
+        }
+    }
+}
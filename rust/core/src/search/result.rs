@@ -1,6 +1,14 @@
+use super::{Explanation, HighlightSpan};
 
-#[derive(Debug)]
+
+#[derive(Debug, Clone)]
 pub struct SearchResult {
-    pub id:    usize,
-    pub title: String,
+    pub id:          usize,
+    pub title:       String,
+    pub fields:      Vec<String>,
+    pub spans:       Vec<HighlightSpan>,
+    pub field_spans: Vec<Vec<HighlightSpan>>,
+    pub explain:     Option<Explanation>,
+    /// Query retyped with another keyboard layout, if the hit was found by it.
+    pub correction:  Option<String>,
 }
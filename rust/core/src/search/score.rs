@@ -1,11 +1,14 @@
-use crate::tokenization::{Word, TextRef};
-use crate::matching::text_match;
+use crate::lang::Lang;
+use crate::tokenization::{Word, TextRef, Synonym};
+use crate::matching::{WordMatch, MatchConfig, MatchBuffers, text_match_synonyms};
+use crate::store::Record;
 use crate::search::Hit;
 
 
 pub const SCORES_SIZE: usize = 9;
 
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ScoreType {
     Chars   = 0,
     Words   = 1,
@@ -19,6 +22,39 @@ pub enum ScoreType {
 }
 
 
+impl ScoreType {
+    pub const ALL: [ScoreType; SCORES_SIZE] = [
+        ScoreType::Chars,
+        ScoreType::Words,
+        ScoreType::Tails,
+        ScoreType::Trans,
+        ScoreType::Fin,
+        ScoreType::Offset,
+        ScoreType::Rating,
+        ScoreType::WordLen,
+        ScoreType::CharLen,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ScoreType::Chars   => "chars",
+            ScoreType::Words   => "words",
+            ScoreType::Tails   => "tails",
+            ScoreType::Trans   => "trans",
+            ScoreType::Fin     => "fin",
+            ScoreType::Offset  => "offset",
+            ScoreType::Rating  => "rating",
+            ScoreType::WordLen => "word_len",
+            ScoreType::CharLen => "char_len",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ScoreType> {
+        Self::ALL.iter().copied().find(|score| score.name() == name)
+    }
+}
+
+
 #[derive(Debug, Clone)]
 pub struct Scores([isize; SCORES_SIZE]);
 
@@ -53,11 +89,24 @@ impl Default for Scores {
 }
 
 
-pub fn score(query: &TextRef, hit: &mut Hit) {
-    let (rmatches, qmatches) = text_match(&hit.title, &query);
+pub fn score(
+    query:    &TextRef,
+    synonyms: &[Synonym],
+    lang:     &Lang,
+    config:   &MatchConfig,
+    buffers:  &mut MatchBuffers,
+    hit:      &mut Hit,
+) {
+    let (rmatches, qmatches) = text_match_synonyms(&hit.title, query, synonyms, lang, config, buffers);
     hit.rmatches = rmatches;
     hit.qmatches = qmatches;
 
+    for field in hit.fields.iter_mut() {
+        let (rmatches, qmatches) = text_match_synonyms(&field.text, query, synonyms, lang, config, buffers);
+        field.rmatches = rmatches;
+        field.qmatches = qmatches;
+    }
+
     hit.scores[ScoreType::Chars]   = score_chars_up(hit);
     hit.scores[ScoreType::Words]   = score_words_up(hit);
     hit.scores[ScoreType::Tails]   = score_tails_down(hit);
@@ -70,18 +119,59 @@ pub fn score(query: &TextRef, hit: &mut Hit) {
 }
 
 
+/// Scores a hit of the record gets for an empty query, only the record ones differ.
+pub fn record_scores(record: &Record) -> Scores {
+    let mut scores = Scores::default();
+    scores[ScoreType::Fin]     = 1;
+    scores[ScoreType::Rating]  = record.rating as isize;
+    scores[ScoreType::WordLen] = -(record.title.words.len() as isize);
+    scores[ScoreType::CharLen] = -(record.title.words.iter().map(|w| w.len()).sum::<usize>() as isize);
+    scores
+}
+
+
 pub fn score_chars_up(hit: &Hit) -> isize {
-    hit.rmatches
+    let title = hit.rmatches
         .iter()
         .map(|m| m.match_len() - 2 * (m.typos.ceil() as usize))
-        .sum::<usize>() as isize
+        .sum::<usize>() as isize;
+    let fields = field_qmatches(hit)
+        .iter()
+        .map(|(m, weight)| {
+            let chars = m.match_len() as isize - 2 * (m.typos.ceil() as isize);
+            (chars as f64 * weight).round() as isize
+        })
+        .sum::<isize>();
+    title + fields
 }
 
 
 pub fn score_words_up(hit: &Hit) -> isize {
-    hit.rmatches.iter()
+    let title = hit.rmatches.iter()
         .filter(|m| !m.func)
-        .count() as isize
+        .count() as isize;
+    let fields = field_qmatches(hit)
+        .iter()
+        .filter(|(m, weight)| !m.func && *weight > 0.0)
+        .count() as isize;
+    title + fields
+}
+
+
+/// Query word matches found in extra fields, but not in the title.
+/// A word matching several fields is given to the one with the highest weight, the first of equal ones.
+fn field_qmatches<'b>(hit: &'b Hit) -> Vec<(&'b WordMatch, f64)> {
+    let mut result: Vec<(&WordMatch, f64)> = Vec::new();
+    for field in hit.fields.iter() {
+        for qmatch in field.qmatches.iter() {
+            if hit.qmatches.iter().any(|m| m.offset == qmatch.offset) { continue; }
+            match result.iter_mut().find(|(m, _)| m.offset == qmatch.offset) {
+                Some(best) => if field.weight > best.1 { *best = (qmatch, field.weight); },
+                None       => result.push((qmatch, field.weight)),
+            }
+        }
+    }
+    result
 }
 
 
@@ -146,7 +236,8 @@ mod tests {
     use crate::tokenization::tokenize_query;
     use crate::store::Record;
     use crate::search::Hit;
-    use super::{score, ScoreType};
+    use crate::matching::{MatchConfig, MatchBuffers};
+    use super::{score, record_scores, ScoreType};
 
     #[test]
     fn score_chars() {
@@ -158,9 +249,9 @@ mod tests {
         let mut h1 = Hit::from_record(&r1);
         let mut h2 = Hit::from_record(&r2);
         let mut h3 = Hit::from_record(&r3);
-        score(&q.to_ref(), &mut h1);
-        score(&q.to_ref(), &mut h2);
-        score(&q.to_ref(), &mut h3);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h1);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h2);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h3);
         assert_eq!(h1.scores[ScoreType::Chars], 4);
         assert_eq!(h2.scores[ScoreType::Chars], 7);
         assert_eq!(h3.scores[ScoreType::Chars], 0);
@@ -176,15 +267,42 @@ mod tests {
         let q1     = tokenize_query("yellow mailbox", &lang);
         let q2     = tokenize_query("yelow maiblox", &lang);
         let q3     = tokenize_query("yellow mail", &lang);
-        score(&q1.to_ref(), &mut h1);
-        score(&q2.to_ref(), &mut h2);
-        score(&q3.to_ref(), &mut h3);
+        score(&q1.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h1);
+        score(&q2.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h2);
+        score(&q3.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h3);
         assert_eq!(h1.scores[ScoreType::Chars], 13);
         assert_eq!(h2.scores[ScoreType::Chars], 9);
         assert_eq!(h3.scores[ScoreType::Chars], 10);
     }
 
 
+    #[test]
+    fn score_chars_fields() {
+        let lang       = Lang::new();
+        let q          = tokenize_query("acme", &lang);
+        let mut r      = Record::new(10, "toothbrush", 0, &lang);
+        r.add_field("Acme", &lang);
+        r.add_field("Acme", &lang);
+        let mut h      = Hit::from_record(&r);
+        h.fields[0].weight = 0.5;
+        h.fields[1].weight = 2.0;
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h);
+        assert_eq!(h.scores[ScoreType::Chars], 8);
+        assert_eq!(h.scores[ScoreType::Words], 1);
+    }
+
+    #[test]
+    fn score_empty_query() {
+        let lang  = lang_english();
+        let q     = tokenize_query("", &lang);
+        let mut r = Record::new(10, "small yellow metal mailbox", 7, &lang);
+        r.add_field("Acme", &lang);
+        let mut h = Hit::from_record(&r);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h);
+        let scores = record_scores(&r);
+        assert_eq!(h.scores.iter().collect::<Vec<_>>(), scores.iter().collect::<Vec<_>>());
+    }
+
     #[test]
     fn score_chars_regress_1() {
         let lang   = lang_english();
@@ -193,8 +311,8 @@ mod tests {
         let r2     = Record::new(20, "orange",   0, &lang);
         let mut h1 = Hit::from_record(&r1);
         let mut h2 = Hit::from_record(&r2);
-        score(&q.to_ref(), &mut h1);
-        score(&q.to_ref(), &mut h2);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h1);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h2);
         assert!(h1.scores[ScoreType::Chars] > h2.scores[ScoreType::Chars]);
     }
 
@@ -212,10 +330,10 @@ mod tests {
         let mut h2 = Hit::from_record(&r2);
         let mut h3 = Hit::from_record(&r3);
         let mut h4 = Hit::from_record(&r4);
-        score(&q.to_ref(), &mut h1);
-        score(&q.to_ref(), &mut h2);
-        score(&q.to_ref(), &mut h3);
-        score(&q.to_ref(), &mut h4);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h1);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h2);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h3);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h4);
         assert_eq!(h1.scores[ScoreType::Tails], -0);
         assert_eq!(h2.scores[ScoreType::Tails], -1);
         assert_eq!(h3.scores[ScoreType::Tails], -1);
@@ -232,11 +350,42 @@ mod tests {
         let q1     = tokenize_query("smal mailbox", &lang);
         let q2     = tokenize_query("yelow mailbox", &lang);
         let q3     = tokenize_query("metol maiblox", &lang);
-        score(&q1.to_ref(), &mut h1);
-        score(&q2.to_ref(), &mut h2);
-        score(&q3.to_ref(), &mut h3);
+        score(&q1.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h1);
+        score(&q2.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h2);
+        score(&q3.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h3);
         assert_eq!(h1.scores[ScoreType::Offset], -0);
         assert_eq!(h2.scores[ScoreType::Offset], -1);
         assert_eq!(h3.scores[ScoreType::Offset], -2);
     }
+
+    #[test]
+    fn score_fields_weight() {
+        let lang   = Lang::new();
+        let q      = tokenize_query("nike shoes", &lang);
+        let mut r1 = Record::new(10, "running shoes", 0, &lang);
+        let mut r2 = Record::new(20, "running shoes", 0, &lang);
+        r1.add_field("Nike", &lang);
+        r2.add_field("Nike", &lang);
+        let mut h1 = Hit::from_record(&r1);
+        let mut h2 = Hit::from_record(&r2);
+        h2.fields[0].weight = 0.5;
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h1);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h2);
+        assert_eq!(h1.scores[ScoreType::Chars], 9);
+        assert_eq!(h2.scores[ScoreType::Chars], 7);
+        assert_eq!(h1.scores[ScoreType::Words], 2);
+        assert_eq!(h2.scores[ScoreType::Words], 2);
+    }
+
+    #[test]
+    fn score_fields_title_first() {
+        let lang   = Lang::new();
+        let q      = tokenize_query("shoes", &lang);
+        let mut r  = Record::new(10, "running shoes", 0, &lang);
+        r.add_field("shoes", &lang);
+        let mut h  = Hit::from_record(&r);
+        score(&q.to_ref(), &[], &lang, &MatchConfig::default(), &mut MatchBuffers::new(), &mut h);
+        assert_eq!(h.scores[ScoreType::Chars], 5);
+        assert_eq!(h.scores[ScoreType::Words], 1);
+    }
 }
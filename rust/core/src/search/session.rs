@@ -0,0 +1,229 @@
+use crate::store::{Store, Candidates};
+use super::{Filter, FacetCount, SearchResult};
+
+
+/// Candidates of the last query, so a query typed further, like "elec" after "ele",
+/// looks up trigrams it shares with the last one only for records having any of them, instead of scanning the whole index.
+/// Others have none of those trigrams, so hits are the same as of `Store::search_text`.
+/// Only a longer last word narrows, a new word shares too few trigrams with the last query to save much.
+#[derive(Debug, Clone, Default)]
+pub struct SearchSession {
+    query:      String,
+    version:    usize,
+    generation: usize,
+    candidates: Option<Candidates>,
+}
+
+
+impl SearchSession {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Forgets the last query, so the next search scans the whole index.
+    pub fn reset(&mut self) {
+        self.candidates = None;
+    }
+
+    /// Candidates of the last query, unless records or settings of the store changed
+    /// or `query` doesn't extend the last word of the last one.
+    fn narrowing(&self, store: &Store, query: &str) -> Option<&Candidates> {
+        if self.version != store.version || self.generation != store.generation || !query.starts_with(&self.query) {
+            return None;
+        }
+        let joined   = self.query.ends_with(char::is_alphanumeric);
+        let extended = query[self.query.len() ..].chars().all(char::is_alphanumeric);
+        if joined && extended { self.candidates.as_ref() } else { None }
+    }
+}
+
+
+impl Store {
+    /// Same as `search_text`, counting trigrams with candidates of the session when `query` extends its last query.
+    /// Pages found in `self.cache` are returned as is and reset the session.
+    pub fn search_session(
+        &self,
+        session: &mut SearchSession,
+        query:   &str,
+        filter:  Option<&Filter>,
+        offset:  usize,
+        limit:   usize,
+    ) -> (Vec<SearchResult>, Vec<FacetCount>) {
+        let queries = self.tokenize_query(query);
+        let queries = queries.iter().map(|q| q.to_ref()).collect::<Vec<_>>();
+        if queries[0].is_empty() {
+            session.reset();
+            return self.search_text(query, filter, offset, limit);
+        }
+
+        // Candidates of a cached query are unknown, so the next one starts over.
+        if let Some(found) = self.cached(query, filter, offset, limit) {
+            session.reset();
+            return found;
+        }
+
+        // Like `search_text`, layouts are retried counting hits past the page, and only the variants are searched again.
+        let retry      = self.layout_retry > 0;
+        let within     = session.narrowing(self, query);
+        let mut counts = self.take_counts();
+        let found      = if retry {
+            self.search_within(&queries, filter, 0, offset.saturating_add(limit), within, &mut counts)
+        } else {
+            self.search_within(&queries, filter, offset, limit, within, &mut counts)
+        };
+        let grams      = Self::query_grams(&queries, &self.query_synonyms(&queries));
+        let candidates = Candidates::new(grams, &counts);
+        self.give_counts(counts);
+        *session = SearchSession {
+            query:      query.to_string(),
+            version:    self.version,
+            generation: self.generation,
+            candidates: Some(candidates),
+        };
+
+        let page = if retry {
+            self.retry_layouts(query, filter, offset, limit, found)
+        } else {
+            (found.0, found.1)
+        };
+        self.cache_page(query, filter, offset, limit, &page);
+        page
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{lang_english, lang_russian};
+    use crate::store::{Store, Record};
+    use super::SearchSession;
+
+    fn get_store() -> Store {
+        let mut store = Store::new();
+        store.lang = lang_english();
+        store.add(Record::new(10, "Electric toothbrush", 10, &store.lang));
+        store.add(Record::new(20, "Elegant dress",       20, &store.lang));
+        store.add(Record::new(30, "Metal mailbox",       30, &store.lang));
+        store
+    }
+
+    fn search(store: &Store, session: &mut SearchSession, query: &str) -> Vec<String> {
+        let (results, _) = store.search_session(session, query, None, 0, 10);
+        results.into_iter().map(|r| r.title).collect()
+    }
+
+    fn candidates(session: &SearchSession) -> Option<Vec<usize>> {
+        session.candidates.as_ref().map(|c| c.ixs.clone())
+    }
+
+    #[test]
+    fn session_narrowing() {
+        let store       = get_store();
+        let mut session = SearchSession::new();
+        assert_eq!(search(&store, &mut session, "ele"),   vec!["[Ele]gant dress", "[Ele]ctric toothbrush"]);
+        assert_eq!(candidates(&session), Some(vec![0, 1]));
+        assert_eq!(search(&store, &mut session, "elec"),  vec!["[Elec]tric toothbrush"]);
+        assert_eq!(candidates(&session), Some(vec![0, 1]));
+        assert_eq!(search(&store, &mut session, "elect"), vec!["[Elect]ric toothbrush"]);
+        assert_eq!(candidates(&session), Some(vec![0, 1]));
+    }
+
+    #[test]
+    fn session_fallback() {
+        let mut store   = get_store();
+        let mut session = SearchSession::new();
+        search(&store, &mut session, "ele");
+        assert_eq!(search(&store, &mut session, "mail"), vec!["Metal [mail]box"]);
+        assert_eq!(candidates(&session), Some(vec![2]));
+        store.add(Record::new(40, "Mailbox lock", 40, &store.lang));
+        assert_eq!(search(&store, &mut session, "mailb"), vec!["[Mailb]ox lock", "Metal [mailb]ox"]);
+        assert_eq!(search(&store, &mut session, "mailbox electric"), vec![
+            "[Electric] toothbrush",
+            "[Mailbox] lock",
+            "Metal [mailbox]",
+        ]);
+        assert_eq!(search(&store, &mut session, ""), vec![
+            "Mailbox lock",
+            "Metal mailbox",
+            "Elegant dress",
+            "Electric toothbrush",
+        ]);
+        assert_eq!(candidates(&session), None);
+    }
+
+    #[test]
+    fn session_missed() {
+        let mut store   = get_store();
+        let mut session = SearchSession::new();
+        store.add(Record::new(40, "Lectern stand", 40, &store.lang));
+        assert_eq!(search(&store, &mut session, "ele"),  vec!["[Ele]gant dress", "[Ele]ctric toothbrush"]);
+        assert_eq!(search(&store, &mut session, "elec"), vec!["[Elec]tric toothbrush", "[Lec]tern stand"]);
+        assert_eq!(candidates(&session), Some(vec![0, 1, 3]));
+    }
+
+    #[test]
+    fn session_same_hits() {
+        let mut store = Store::new();
+        store.lang = lang_english();
+        let words = ["electric", "elegant", "selector", "lectern", "toothbrush", "tooth", "metal", "dress"];
+        for id in 0 .. 200 {
+            let title = format!("{} {}", words[id % words.len()], words[id * 7 / 3 % words.len()]);
+            store.add(Record::new(id, &title, id % 13, &store.lang));
+        }
+        let mut session = SearchSession::new();
+        let query       = "electric toothbrush";
+        for end in 1 ..= query.len() {
+            for &(offset, limit) in &[(0, 2), (0, 10), (10, 10)] {
+                let (found, _)    = store.search_session(&mut session, &query[.. end], None, offset, limit);
+                let (expected, _) = store.search_text(&query[.. end], None, offset, limit);
+                assert_eq!(format!("{:?}", found), format!("{:?}", expected), "{:?} {} {}", &query[.. end], offset, limit);
+            }
+        }
+    }
+
+    #[test]
+    fn session_layout_retry() {
+        let mut store = Store::new();
+        store.lang = lang_english();
+        let ru = store.add_lang(lang_russian());
+        let mut record = Record::new(10, "Коврик для мыши", 10, store.get_lang(ru).unwrap());
+        record.lang = ru;
+        store.add(record);
+        store.add(Record::new(20, "Rjdbr", 20, &store.lang));
+        store.layout_retry = 2;
+        let mut session = SearchSession::new();
+        assert_eq!(search(&store, &mut session, "rjd"),    vec!["[Rjd]br", "[Ков]рик для мыши"]);
+        assert_eq!(search(&store, &mut session, "rjdhbr"), vec!["[Rjdbr]", "[Коврик] для мыши"]);
+        assert_eq!(candidates(&session), Some(vec![1]));
+        for query in &["rjd", "rjdh", "rjdhbr"] {
+            let found    = store.search_session(&mut session, query, None, 1, 1);
+            let expected = store.search_text(query, None, 1, 1);
+            assert_eq!(format!("{:?}", found), format!("{:?}", expected));
+        }
+    }
+
+    #[test]
+    fn session_generation() {
+        let mut store   = get_store();
+        let mut session = SearchSession::new();
+        search(&store, &mut session, "ele");
+        assert!(session.narrowing(&store, "elec").is_some());
+        store.config_changed();
+        assert!(session.narrowing(&store, "elec").is_none());
+    }
+
+    #[test]
+    fn session_cache() {
+        let store       = get_store();
+        let mut session = SearchSession::new();
+        store.cache.lock().unwrap().set_capacity(10);
+        search(&store, &mut session, "ele");
+        search(&store, &mut session, "elec");
+        assert_eq!(search(&store, &mut session, "ele"), vec!["[Ele]gant dress", "[Ele]ctric toothbrush"]);
+        assert_eq!(candidates(&session), None);
+        assert_eq!(search(&store, &mut session, "eleg"), vec!["[Eleg]ant dress"]);
+        assert_eq!(candidates(&session), Some(vec![0, 1]));
+        let cache = store.cache.lock().unwrap();
+        assert_eq!((cache.hits, cache.misses), (1, 3));
+    }
+}
@@ -1,11 +1,23 @@
 use std::cmp::Ordering;
-use crate::search::Hit;
+use crate::search::{Hit, RankingConfig};
+use crate::search::score::Scores;
 
 
-pub fn compare_hits(hit1: &Hit, hit2: &Hit) -> Ordering {
-    hit1.scores.iter()
-        .zip(hit2.scores.iter())
-        .map(|(s1, s2)| s2.cmp(s1))
-        .find(|&ord| ord != Ordering::Equal)
+pub fn compare_hits(hit1: &Hit, hit2: &Hit, ranking: &RankingConfig) -> Ordering {
+    compare_scores(&hit1.scores, &hit2.scores, ranking)
+        .then_with(|| hit1.ix.cmp(&hit2.ix))
+}
+
+
+pub fn compare_scores(scores1: &Scores, scores2: &Scores, ranking: &RankingConfig) -> Ordering {
+    let sum1 = ranking.weighted_sum(scores1);
+    let sum2 = ranking.weighted_sum(scores2);
+    sum2.partial_cmp(&sum1)
         .unwrap_or(Ordering::Equal)
+        .then_with(|| {
+            ranking.order.iter()
+                .map(|&score| scores2[score].cmp(&scores1[score]))
+                .find(|&ord| ord != Ordering::Equal)
+                .unwrap_or(Ordering::Equal)
+        })
 }
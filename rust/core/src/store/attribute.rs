@@ -0,0 +1,27 @@
+/// Filterable record value, not used for text matching.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Attribute {
+    Int(i64),
+    Keywords(Vec<String>),
+}
+
+
+impl Attribute {
+    pub fn keywords(keywords: &[&str]) -> Self {
+        Attribute::Keywords(keywords.iter().map(|k| k.to_string()).collect())
+    }
+
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            Attribute::Int(value) => Some(*value),
+            _                     => None,
+        }
+    }
+
+    pub fn has_keyword(&self, keyword: &str) -> bool {
+        match self {
+            Attribute::Keywords(keywords) => keywords.iter().any(|k| k == keyword),
+            _                             => false,
+        }
+    }
+}
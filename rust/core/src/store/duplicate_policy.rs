@@ -0,0 +1,18 @@
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DuplicatePolicy {
+    /// Ignore a record if the store already has one with the same id.
+    Reject,
+    /// Replace the existing record with the same id.
+    Replace,
+    /// Store both records, each of them can appear in search results.
+    KeepBoth,
+}
+
+
+// Not derived, `#[default]` on a variant needs a newer toolchain than the crate supports.
+#[allow(clippy::derivable_impls)]
+impl Default for DuplicatePolicy {
+    fn default() -> Self {
+        DuplicatePolicy::KeepBoth
+    }
+}
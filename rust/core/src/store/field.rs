@@ -0,0 +1,16 @@
+pub const DEFAULT_FIELD_WEIGHT: f64 = 1.0;
+
+
+/// Extra text field of a record, title weight is always 1.0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub name:   String,
+    pub weight: f64,
+}
+
+
+impl Field {
+    pub fn new(name: &str, weight: f64) -> Self {
+        Self { name: name.to_string(), weight }
+    }
+}
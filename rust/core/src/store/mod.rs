@@ -1,9 +1,17 @@
 mod record;
+mod field;
+mod attribute;
 mod store;
 mod trigram_index;
+mod duplicate_policy;
+mod snapshot;
 
 pub use record::Record;
+pub use field::{Field, DEFAULT_FIELD_WEIGHT};
+pub use attribute::Attribute;
 pub use store::Store;
-pub use trigram_index::TrigramIndex;
+pub use duplicate_policy::DuplicatePolicy;
+pub use snapshot::SnapshotError;
+pub use trigram_index::{TrigramIndex, Candidates};
 
 pub static DEFAULT_LIMIT: usize = 10;
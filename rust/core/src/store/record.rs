@@ -1,5 +1,6 @@
 use crate::tokenization::{TextOwn, tokenize_record};
 use crate::lang::Lang;
+use super::Attribute;
 
 
 #[derive(Debug)]
@@ -7,7 +8,11 @@ pub struct Record {
     pub ix:     usize,
     pub id:     usize,
     pub title:  TextOwn,
+    pub fields: Vec<TextOwn>,
+    pub attrs:  Vec<(String, Attribute)>,
     pub rating: usize,
+    /// Index of the store language the texts were tokenized with, see `Store::add_lang`.
+    pub lang:   usize,
 }
 
 
@@ -17,7 +22,39 @@ impl Record {
             ix: 0,
             id,
             title: tokenize_record(source, lang),
+            fields: Vec::new(),
+            attrs: Vec::new(),
             rating,
+            lang: 0,
         }
     }
+
+    pub fn add_field(&mut self, source: &str, lang: &Lang) {
+        self.fields.push(tokenize_record(source, lang));
+    }
+
+    pub fn set_attr(&mut self, name: &str, value: Attribute) {
+        match self.attrs.iter_mut().find(|(n, _)| n == name) {
+            Some((_, attr)) => *attr = value,
+            None            => self.attrs.push((name.to_string(), value)),
+        }
+    }
+
+    pub fn attr(&self, name: &str) -> Option<&Attribute> {
+        self.attrs
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, attr)| attr)
+    }
+
+    pub fn texts(&self) -> impl Iterator<Item=&TextOwn> {
+        Some(&self.title).into_iter().chain(self.fields.iter())
+    }
+
+    pub fn title_string(&self) -> String {
+        self.title.source
+            .iter()
+            .filter(|&&ch| ch != '\0')
+            .collect()
+    }
 }
@@ -0,0 +1,666 @@
+use std::fmt;
+use std::error::Error;
+use std::convert::TryFrom;
+use fnv::{FnvHashMap as HashMap};
+use crate::tokenization::{TextOwn, WordShape};
+use crate::lang::{CharClass, PartOfSpeech, PhoneticKey};
+use super::{Store, Record, Attribute, Field, TrigramIndex};
+
+
+const SNAPSHOT_MAGIC:   &[u8; 4] = b"LSST";
+const SNAPSHOT_VERSION: u32      = 1;
+
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnapshotError {
+    InvalidMagic,
+    UnsupportedVersion(u32),
+    UnexpectedEnd,
+    InvalidValue(&'static str),
+    FieldMismatch(String),
+}
+
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::InvalidMagic            => write!(f, "Not a store snapshot"),
+            SnapshotError::UnsupportedVersion(ver) => write!(f, "Unsupported snapshot version {}", ver),
+            SnapshotError::UnexpectedEnd           => write!(f, "Unexpected end of snapshot"),
+            SnapshotError::InvalidValue(what)      => write!(f, "Invalid {} in snapshot", what),
+            SnapshotError::FieldMismatch(name)     => write!(f, "Snapshot field {:?} differs from store fields", name),
+        }
+    }
+}
+
+
+impl Error for SnapshotError { }
+
+
+impl Store {
+    /// Serializes fields, tokenized records and the trigram index.
+    /// Settings like langs, limit or dividers are not included,
+    /// so record languages should be added in the same order before import.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut writer = ByteWriter::new();
+        writer.bytes(SNAPSHOT_MAGIC);
+        writer.u32(SNAPSHOT_VERSION);
+        write_fields(&mut writer, &self.fields);
+        writer.usize(self.records.len());
+        for record in &self.records {
+            write_record(&mut writer, record);
+        }
+        write_index(&mut writer, &self.index);
+        writer.into_bytes()
+    }
+
+    /// Records can only be in the default language and have no fields, see `load_bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Store, SnapshotError> {
+        let mut store = Store::new();
+        store.load_bytes(bytes)?;
+        Ok(store)
+    }
+
+    /// Replaces records and the index with a snapshot, keeping current settings.
+    /// Fails if a record language is missing from the store,
+    /// or the store fields differ from the exported ones in names, weights or order.
+    pub fn load_bytes(&mut self, bytes: &[u8]) -> Result<(), SnapshotError> {
+        let mut reader = ByteReader::new(bytes);
+        if reader.bytes(SNAPSHOT_MAGIC.len())? != SNAPSHOT_MAGIC {
+            return Err(SnapshotError::InvalidMagic);
+        }
+        let version = reader.u32()?;
+        if version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::UnsupportedVersion(version));
+        }
+        read_fields(&mut reader, &self.fields)?;
+
+        let len = reader.usize()?;
+        let mut records = Vec::with_capacity(min!(len, bytes.len()));
+        for ix in 0 .. len {
+            records.push(read_record(&mut reader, ix, self.fields.len(), self.langs.len())?);
+        }
+        let index = read_index(&mut reader, &records, self.fields.len())?;
+        if !reader.is_empty() {
+            return Err(SnapshotError::InvalidValue("trailing bytes"));
+        }
+
+        self.clear();
+        for record in &records {
+            self.ids.entry(record.id).or_default().push(record.ix);
+        }
+        self.next_ix = records.len();
+        self.records = records;
+        self.index = index;
+        Ok(())
+    }
+}
+
+
+fn write_fields(writer: &mut ByteWriter, fields: &[Field]) {
+    writer.usize(fields.len());
+    for field in fields {
+        writer.string(&field.name);
+        writer.f64(field.weight);
+    }
+}
+
+
+fn read_fields(reader: &mut ByteReader, fields: &[Field]) -> Result<(), SnapshotError> {
+    let len = reader.usize()?;
+    for ix in 0 .. len {
+        let name   = reader.string()?;
+        let weight = reader.f64()?;
+        match fields.get(ix) {
+            Some(field) if field.name == name && field.weight.to_bits() == weight.to_bits() => { },
+            _ => return Err(SnapshotError::FieldMismatch(name)),
+        }
+    }
+    match fields.get(len) {
+        Some(field) => Err(SnapshotError::FieldMismatch(field.name.clone())),
+        None        => Ok(()),
+    }
+}
+
+
+fn write_record(writer: &mut ByteWriter, record: &Record) {
+    let Record { id, title, fields, attrs, rating, lang, .. } = record;
+    writer.usize(*id);
+    writer.usize(*rating);
+    writer.usize(*lang);
+    write_text(writer, title);
+    writer.usize(fields.len());
+    for field in fields {
+        write_text(writer, field);
+    }
+    writer.usize(attrs.len());
+    for (name, attr) in attrs {
+        writer.string(name);
+        write_attr(writer, attr);
+    }
+}
+
+
+fn read_record(reader: &mut ByteReader, ix: usize, n_fields: usize, n_langs: usize) -> Result<Record, SnapshotError> {
+    let id     = reader.usize()?;
+    let rating = reader.usize()?;
+    let lang   = reader.usize()?;
+    if lang > n_langs {
+        return Err(SnapshotError::InvalidValue("record language"));
+    }
+    let title  = read_text(reader)?;
+    let len = reader.usize()?;
+    if len != n_fields {
+        return Err(SnapshotError::InvalidValue("record fields"));
+    }
+    let mut fields = Vec::with_capacity(len);
+    for _ in 0 .. len {
+        fields.push(read_text(reader)?);
+    }
+    let len = reader.usize()?;
+    let mut attrs = Vec::with_capacity(min!(len, reader.bytes.len()));
+    for _ in 0 .. len {
+        let name = reader.string()?;
+        attrs.push((name, read_attr(reader)?));
+    }
+    Ok(Record { ix, id, title, fields, attrs, rating, lang })
+}
+
+
+fn write_attr(writer: &mut ByteWriter, attr: &Attribute) {
+    match attr {
+        Attribute::Int(value) => {
+            writer.u8(0);
+            writer.i64(*value);
+        },
+        Attribute::Keywords(keywords) => {
+            writer.u8(1);
+            writer.usize(keywords.len());
+            for keyword in keywords {
+                writer.string(keyword);
+            }
+        },
+    }
+}
+
+
+fn read_attr(reader: &mut ByteReader) -> Result<Attribute, SnapshotError> {
+    match reader.u8()? {
+        0 => Ok(Attribute::Int(reader.i64()?)),
+        1 => {
+            let len = reader.usize()?;
+            let mut keywords = Vec::with_capacity(min!(len, reader.bytes.len()));
+            for _ in 0 .. len {
+                keywords.push(reader.string()?);
+            }
+            Ok(Attribute::Keywords(keywords))
+        },
+        _ => Err(SnapshotError::InvalidValue("attribute type")),
+    }
+}
+
+
+fn write_text(writer: &mut ByteWriter, text: &TextOwn) {
+    writer.chars(&text.source);
+    writer.chars(&text.chars);
+    writer.usize(text.classes.len());
+    for &class in &text.classes {
+        writer.u8(class_to_u8(class));
+    }
+    writer.usize(text.words.len());
+    for word in &text.words {
+        writer.usize(word.offset);
+        writer.usize(word.slice.0);
+        writer.usize(word.slice.1);
+        writer.usize(word.stem);
+        writer.u8(pos_to_u8(word.pos));
+        writer.bytes(&word.sound.map(|key| key.to_bytes()).unwrap_or_default());
+        writer.u8(word.fin as u8);
+    }
+}
+
+
+fn read_text(reader: &mut ByteReader) -> Result<TextOwn, SnapshotError> {
+    let source = reader.chars()?;
+    let chars  = reader.chars()?;
+    if source.len() != chars.len() {
+        return Err(SnapshotError::InvalidValue("text length"));
+    }
+
+    let len = reader.usize()?;
+    if len != chars.len() {
+        return Err(SnapshotError::InvalidValue("text length"));
+    }
+    let mut classes = Vec::with_capacity(len);
+    for _ in 0 .. len {
+        classes.push(class_from_u8(reader.u8()?)?);
+    }
+
+    let len = reader.usize()?;
+    let mut words = Vec::with_capacity(min!(len, chars.len()));
+    for _ in 0 .. len {
+        let offset = reader.usize()?;
+        let slice  = (reader.usize()?, reader.usize()?);
+        let stem   = reader.usize()?;
+        let pos    = pos_from_u8(reader.u8()?)?;
+        let sound  = read_sound(reader)?;
+        let fin    = reader.u8()? != 0;
+        if slice.0 > slice.1 || slice.1 > chars.len() || stem > slice.1 - slice.0 {
+            return Err(SnapshotError::InvalidValue("word slice"));
+        }
+        words.push(WordShape { offset, slice, stem, pos, sound, fin });
+    }
+
+    Ok(TextOwn { words, source, chars, classes })
+}
+
+
+fn read_sound(reader: &mut ByteReader) -> Result<Option<PhoneticKey>, SnapshotError> {
+    let mut bytes = [0; 6];
+    bytes.copy_from_slice(reader.bytes(6)?);
+    Ok(PhoneticKey::from_bytes(bytes))
+}
+
+
+fn write_index(writer: &mut ByteWriter, index: &TrigramIndex) {
+    write_dict(writer, &index.dict);
+    writer.usize(index.fields.len());
+    for dict in &index.fields {
+        write_dict(writer, dict);
+    }
+}
+
+
+fn write_dict(writer: &mut ByteWriter, dict: &HashMap<[char; 3], Vec<usize>>) {
+    let mut grams = dict.iter().collect::<Vec<_>>();
+    grams.sort_unstable_by_key(|(gram, _)| **gram);
+    writer.usize(grams.len());
+    for (gram, ixs) in grams {
+        for &ch in gram {
+            writer.u32(ch as u32);
+        }
+        writer.usize(ixs.len());
+        let mut prev = 0;
+        for &ix in ixs {
+            writer.usize(ix - prev);
+            prev = ix;
+        }
+    }
+}
+
+
+fn read_index(reader: &mut ByteReader, records: &[Record], n_fields: usize) -> Result<TrigramIndex, SnapshotError> {
+    let n_records = records.len();
+    let mut index = TrigramIndex::new();
+    index.len  = n_records;
+    index.dict = read_dict(reader, n_records)?;
+    let len = reader.usize()?;
+    if len > n_fields {
+        return Err(SnapshotError::InvalidValue("index fields"));
+    }
+    for _ in 0 .. len {
+        index.fields.push(read_dict(reader, n_records)?);
+    }
+    Ok(index)
+}
+
+
+fn read_dict(reader: &mut ByteReader, len: usize) -> Result<HashMap<[char; 3], Vec<usize>>, SnapshotError> {
+    let mut dict = HashMap::default();
+    let n_grams = reader.usize()?;
+    for _ in 0 .. n_grams {
+        let gram = [reader.char()?, reader.char()?, reader.char()?];
+        let n_ixs = reader.usize()?;
+        let mut ixs  = Vec::with_capacity(min!(n_ixs, len));
+        let mut prev = 0usize;
+        for i in 0 .. n_ixs {
+            let delta = reader.usize()?;
+            if i > 0 && delta == 0 {
+                return Err(SnapshotError::InvalidValue("index posting"));
+            }
+            let ix = match prev.checked_add(delta) {
+                Some(ix) if ix < len => ix,
+                _ => return Err(SnapshotError::InvalidValue("index posting")),
+            };
+            ixs.push(ix);
+            prev = ix;
+        }
+        dict.insert(gram, ixs);
+    }
+    Ok(dict)
+}
+
+
+fn class_to_u8(class: CharClass) -> u8 {
+    match class {
+        CharClass::Any         => 0,
+        CharClass::Control     => 1,
+        CharClass::Whitespace  => 2,
+        CharClass::Punctuation => 3,
+        CharClass::NotAlpha    => 4,
+        CharClass::NotAlphaNum => 5,
+        CharClass::Consonant   => 6,
+        CharClass::Vowel       => 7,
+    }
+}
+
+
+fn class_from_u8(code: u8) -> Result<CharClass, SnapshotError> {
+    match code {
+        0 => Ok(CharClass::Any),
+        1 => Ok(CharClass::Control),
+        2 => Ok(CharClass::Whitespace),
+        3 => Ok(CharClass::Punctuation),
+        4 => Ok(CharClass::NotAlpha),
+        5 => Ok(CharClass::NotAlphaNum),
+        6 => Ok(CharClass::Consonant),
+        7 => Ok(CharClass::Vowel),
+        _ => Err(SnapshotError::InvalidValue("char class")),
+    }
+}
+
+
+fn pos_to_u8(pos: Option<PartOfSpeech>) -> u8 {
+    match pos {
+        None                            => 0,
+        Some(PartOfSpeech::Noun)        => 1,
+        Some(PartOfSpeech::Pronoun)     => 2,
+        Some(PartOfSpeech::Verb)        => 3,
+        Some(PartOfSpeech::Adjective)   => 4,
+        Some(PartOfSpeech::Adverb)      => 5,
+        Some(PartOfSpeech::Preposition) => 6,
+        Some(PartOfSpeech::Conjunction) => 7,
+        Some(PartOfSpeech::Particle)    => 8,
+        Some(PartOfSpeech::Intejection) => 9,
+        Some(PartOfSpeech::Article)     => 10,
+    }
+}
+
+
+fn pos_from_u8(code: u8) -> Result<Option<PartOfSpeech>, SnapshotError> {
+    match code {
+        0  => Ok(None),
+        1  => Ok(Some(PartOfSpeech::Noun)),
+        2  => Ok(Some(PartOfSpeech::Pronoun)),
+        3  => Ok(Some(PartOfSpeech::Verb)),
+        4  => Ok(Some(PartOfSpeech::Adjective)),
+        5  => Ok(Some(PartOfSpeech::Adverb)),
+        6  => Ok(Some(PartOfSpeech::Preposition)),
+        7  => Ok(Some(PartOfSpeech::Conjunction)),
+        8  => Ok(Some(PartOfSpeech::Particle)),
+        9  => Ok(Some(PartOfSpeech::Intejection)),
+        10 => Ok(Some(PartOfSpeech::Article)),
+        _  => Err(SnapshotError::InvalidValue("part of speech")),
+    }
+}
+
+
+/// Integers are written as LEB128 varints, so ascii text takes a byte per char.
+struct ByteWriter {
+    bytes: Vec<u8>,
+}
+
+
+impl ByteWriter {
+    fn new() -> Self {
+        Self { bytes: Vec::new() }
+    }
+
+    fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.bytes.extend_from_slice(bytes);
+    }
+
+    fn u8(&mut self, x: u8) {
+        self.bytes.push(x);
+    }
+
+    fn u32(&mut self, x: u32) {
+        self.u64(x as u64);
+    }
+
+    fn usize(&mut self, x: usize) {
+        self.u64(x as u64);
+    }
+
+    fn u64(&mut self, mut x: u64) {
+        while x >= 0x80 {
+            self.bytes.push((x as u8 & 0x7f) | 0x80);
+            x >>= 7;
+        }
+        self.bytes.push(x as u8);
+    }
+
+    /// Zigzag encoding keeps small negative numbers short.
+    fn i64(&mut self, x: i64) {
+        self.u64(((x << 1) ^ (x >> 63)) as u64);
+    }
+
+    fn f64(&mut self, x: f64) {
+        self.bytes(&x.to_bits().to_le_bytes());
+    }
+
+    fn chars(&mut self, chars: &[char]) {
+        self.usize(chars.len());
+        for &ch in chars {
+            self.u32(ch as u32);
+        }
+    }
+
+    fn string(&mut self, string: &str) {
+        self.usize(string.len());
+        self.bytes(string.as_bytes());
+    }
+}
+
+
+struct ByteReader<'a> {
+    bytes: &'a [u8],
+}
+
+
+impl<'a> ByteReader<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
+        Self { bytes }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
+    fn bytes(&mut self, len: usize) -> Result<&'a [u8], SnapshotError> {
+        if self.bytes.len() < len {
+            return Err(SnapshotError::UnexpectedEnd);
+        }
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SnapshotError> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SnapshotError> {
+        u32::try_from(self.u64()?).map_err(|_| SnapshotError::InvalidValue("integer"))
+    }
+
+    fn usize(&mut self) -> Result<usize, SnapshotError> {
+        usize::try_from(self.u64()?).map_err(|_| SnapshotError::InvalidValue("integer"))
+    }
+
+    fn u64(&mut self) -> Result<u64, SnapshotError> {
+        let mut x = 0;
+        for shift in (0 .. 64).step_by(7) {
+            let byte = self.u8()?;
+            x |= ((byte & 0x7f) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(x);
+            }
+        }
+        Err(SnapshotError::InvalidValue("integer"))
+    }
+
+    fn i64(&mut self) -> Result<i64, SnapshotError> {
+        let x = self.u64()?;
+        Ok((x >> 1) as i64 ^ -((x & 1) as i64))
+    }
+
+    fn f64(&mut self) -> Result<f64, SnapshotError> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+        Ok(f64::from_bits(u64::from_le_bytes(bytes)))
+    }
+
+    fn char(&mut self) -> Result<char, SnapshotError> {
+        std::char::from_u32(self.u32()?).ok_or(SnapshotError::InvalidValue("char"))
+    }
+
+    fn chars(&mut self) -> Result<Vec<char>, SnapshotError> {
+        let len = self.usize()?;
+        let mut chars = Vec::with_capacity(min!(len, self.bytes.len()));
+        for _ in 0 .. len {
+            chars.push(self.char()?);
+        }
+        Ok(chars)
+    }
+
+    fn string(&mut self) -> Result<String, SnapshotError> {
+        let len   = self.usize()?;
+        let bytes = self.bytes(len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| SnapshotError::InvalidValue("string"))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenization::tokenize_query;
+    use crate::lang::lang_english;
+    use super::{Store, Record, Attribute, SnapshotError, SNAPSHOT_VERSION};
+    use super::{ByteWriter, ByteReader, read_dict};
+
+    fn new_store() -> Store {
+        let mut store = Store::new();
+        store.lang = lang_english();
+        store.add_lang(lang_english());
+        store.add_field("brand", 1.0);
+        store
+    }
+
+    fn new_record(id: usize, title: &str, brand: &str, store: &Store) -> Record {
+        let mut record = Record::new(id, title, id, &store.lang);
+        record.add_field(brand, &store.lang);
+        record
+    }
+
+    fn get_store() -> Store {
+        let mut store = new_store();
+        store.add(new_record(10, "brown plush bear",     "", &store));
+        store.add(new_record(20, "the metal detector",   "", &store));
+        store.add(new_record(30, "yellow metal mailbox", "", &store));
+        store.add(new_record(40, "Passstraße",           "", &store));
+        let mut record = new_record(50, "wi-fi router", "Netgear", &store);
+        record.set_attr("price", Attribute::Int(-35));
+        record.set_attr("tags", Attribute::keywords(&["network", "wireless"]));
+        record.lang = 1;
+        store.add(record);
+        store
+    }
+
+    fn search_titles(store: &Store, query: &str) -> Vec<(String, Vec<String>)> {
+        let query = tokenize_query(query, &store.lang);
+        store.search(&query.to_ref(), None).into_iter().map(|r| (r.title, r.fields)).collect()
+    }
+
+    #[test]
+    fn snapshot_roundtrip() {
+        let store1     = get_store();
+        let mut store2 = new_store();
+        store2.load_bytes(&store1.to_bytes()).unwrap();
+        assert_eq!(store2.next_ix, 5);
+        assert_eq!(store2.get(30).map(|r| r.ix), Some(2));
+        for (r1, r2) in store1.records.iter().zip(store2.records.iter()) {
+            assert_eq!((r1.ix, r1.id, r1.rating, r1.lang), (r2.ix, r2.id, r2.rating, r2.lang));
+            assert_eq!(r1.title, r2.title);
+            assert_eq!(r1.fields, r2.fields);
+            assert_eq!(r1.attrs, r2.attrs);
+        }
+        for query in &["metal", "the", "passstr", "netgear", ""] {
+            assert_eq!(search_titles(&store1, query), search_titles(&store2, query));
+        }
+    }
+
+    #[test]
+    fn snapshot_keeps_settings() {
+        let mut store = new_store();
+        store.limit = 1;
+        store.load_bytes(&get_store().to_bytes()).unwrap();
+        assert_eq!(store.records.len(), 5);
+        assert_eq!(store.limit, 1);
+        assert_eq!(search_titles(&store, "metal").len(), 1);
+    }
+
+    #[test]
+    fn snapshot_invalid_magic() {
+        let result = Store::from_bytes(b"nope");
+        assert_eq!(result.err(), Some(SnapshotError::InvalidMagic));
+    }
+
+    #[test]
+    fn snapshot_invalid_version() {
+        let mut bytes = get_store().to_bytes();
+        bytes[4] = SNAPSHOT_VERSION as u8 + 1;
+        let result = Store::from_bytes(&bytes);
+        assert_eq!(result.err(), Some(SnapshotError::UnsupportedVersion(SNAPSHOT_VERSION + 1)));
+    }
+
+    #[test]
+    fn snapshot_invalid_fields() {
+        let bytes = get_store().to_bytes();
+        let mut store = new_store();
+        store.fields[0].weight = 2.0;
+        assert_eq!(store.load_bytes(&bytes).err(), Some(SnapshotError::FieldMismatch("brand".to_string())));
+        store.fields.clear();
+        assert_eq!(store.load_bytes(&bytes).err(), Some(SnapshotError::FieldMismatch("brand".to_string())));
+        store.add_field("model", 1.0);
+        assert_eq!(store.load_bytes(&bytes).err(), Some(SnapshotError::FieldMismatch("brand".to_string())));
+        store.add_field("brand", 1.0);
+        assert_eq!(store.load_bytes(&bytes).err(), Some(SnapshotError::FieldMismatch("brand".to_string())));
+        assert_eq!(store.records.len(), 0);
+    }
+
+    #[test]
+    fn snapshot_invalid_lang() {
+        let mut store = Store::new();
+        store.add_field("brand", 1.0);
+        let result = store.load_bytes(&get_store().to_bytes());
+        assert_eq!(result.err(), Some(SnapshotError::InvalidValue("record language")));
+    }
+
+    #[test]
+    fn snapshot_posting_overflow() {
+        let mut writer = ByteWriter::new();
+        writer.usize(1);
+        for &ch in &['a', 'b', 'c'] {
+            writer.u32(ch as u32);
+        }
+        writer.usize(2);
+        writer.usize(1);
+        writer.usize(usize::MAX);
+        let bytes  = writer.into_bytes();
+        let result = read_dict(&mut ByteReader::new(&bytes), 2);
+        assert_eq!(result.err(), Some(SnapshotError::InvalidValue("index posting")));
+    }
+
+    #[test]
+    fn snapshot_truncated() {
+        let bytes = get_store().to_bytes();
+        for len in 0 .. bytes.len() {
+            assert!(Store::from_bytes(&bytes[..len]).is_err());
+        }
+    }
+}
@@ -1,54 +1,377 @@
-use std::cell::RefCell;
+use std::sync::{Mutex, PoisonError};
+use fnv::{FnvHashMap as HashMap};
 use crate::utils::to_vec;
-use crate::lang::Lang;
-use super::{Record, TrigramIndex, DEFAULT_LIMIT};
+use crate::lang::{Lang, detect_lang};
+use crate::tokenization::{TextOwn, tokenize_query};
+use crate::search::{RankingConfig, ResultCache};
+use crate::matching::MatchConfig;
+use super::{Record, Field, Attribute, TrigramIndex, DuplicatePolicy, DEFAULT_LIMIT, DEFAULT_FIELD_WEIGHT};
 
 
 pub struct Store {
-    pub next_ix:  usize,
-    pub records:  Vec<Record>,
-    pub limit:    usize,
-    pub lang:     Lang,
-    pub dividers: (Vec<char>, Vec<char>),
-    pub index:    RefCell<TrigramIndex>,
-    pub top_ixs:  RefCell<Option<Vec<usize>>>,
+    pub next_ix:      usize,
+    pub records:      Vec<Record>,
+    pub ids:          HashMap<usize, Vec<usize>>,
+    pub duplicates:   DuplicatePolicy,
+    pub fields:       Vec<Field>,
+    pub facets:       Vec<String>,
+    pub limit:        usize,
+    pub ranking:      RankingConfig,
+    pub matching:     MatchConfig,
+    pub explain:      bool,
+    pub lang:         Lang,
+    pub langs:        Vec<Lang>,
+    pub detect:       bool,
+    pub layout_retry: usize,
+    pub dividers:     (Vec<char>, Vec<char>),
+    pub index:        TrigramIndex,
+    pub top_ixs:      Mutex<Option<Vec<usize>>>,
+    /// Changes whenever records are added, removed, replaced or get an attribute.
+    pub version:      usize,
+    /// Changes whenever settings affecting search results change, see `config_changed`.
+    pub generation:   usize,
+    pub cache:        Mutex<ResultCache>,
+    /// Trigram count buffers of finished searches, reused so every search doesn't allocate one per record.
+    pub counts:       Mutex<Vec<Vec<usize>>>,
 }
 
 
 impl Store {
     pub fn new() -> Self {
         Self {
-            next_ix:  0,
-            records:  Vec::new(),
-            limit:    DEFAULT_LIMIT,
-            lang:     Lang::new(),
-            dividers: (vec!['['], vec![']']),
-            index:    RefCell::new(TrigramIndex::new()),
-            top_ixs:  RefCell::new(None),
+            next_ix:      0,
+            records:      Vec::new(),
+            ids:          HashMap::default(),
+            duplicates:   DuplicatePolicy::default(),
+            fields:       Vec::new(),
+            facets:       Vec::new(),
+            limit:        DEFAULT_LIMIT,
+            ranking:      RankingConfig::default(),
+            matching:     MatchConfig::default(),
+            explain:      false,
+            lang:         Lang::new(),
+            langs:        Vec::new(),
+            detect:       false,
+            layout_retry: 0,
+            dividers:     (vec!['['], vec![']']),
+            index:        TrigramIndex::new(),
+            top_ixs:      Mutex::new(None),
+            version:      0,
+            generation:   0,
+            cache:        Mutex::new(ResultCache::new(0)),
+            counts:       Mutex::new(Vec::new()),
         }
     }
 
-    pub fn add(&mut self, mut record: Record) {
-        let Self { next_ix, index, records, .. } = self;
+    pub fn add(&mut self, mut record: Record) -> bool {
+        if self.ids.contains_key(&record.id) {
+            match self.duplicates {
+                DuplicatePolicy::Reject   => return false,
+                DuplicatePolicy::Replace  => return self.update(record),
+                DuplicatePolicy::KeepBoth => { },
+            }
+        }
+        let Self { next_ix, index, records, ids, .. } = self;
         debug_assert!(*next_ix == records.len(), "Invalid store.next_ix");
         record.ix = *next_ix;
-        index.borrow_mut().add(&record);
+        index.add(&record);
+        ids.entry(record.id).or_default().push(record.ix);
         records.push(record);
         *next_ix += 1;
+        self.records_changed();
+        true
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Record> {
+        let &ix = self.ids.get(&id)?.first()?;
+        self.records.get(ix)
+    }
+
+    pub fn remove(&mut self, id: usize) -> Option<Record> {
+        let Self { next_ix, index, records, ids, .. } = self;
+        debug_assert!(*next_ix == records.len(), "Invalid store.next_ix");
+        let ix   = Self::take_ix(ids, id)?;
+        let last = records.len() - 1;
+
+        index.remove(&records[ix]);
+        if ix != last {
+            // The last record takes the place of the removed one.
+            index.remove(&records[last]);
+            records.swap(ix, last);
+            records[ix].ix = ix;
+            index.insert(&records[ix]);
+            for moved_ix in ids.get_mut(&records[ix].id).into_iter().flatten() {
+                if *moved_ix == last { *moved_ix = ix; }
+            }
+        }
+        let removed = records.pop();
+
+        *next_ix -= 1;
+        self.records_changed();
+        removed
+    }
+
+    pub fn update(&mut self, mut record: Record) -> bool {
+        let Self { index, records, ids, .. } = self;
+        let ix = match ids.get(&record.id).and_then(|ixs| ixs.first()) {
+            Some(&ix) => ix,
+            None      => return false,
+        };
+        record.ix = ix;
+        index.remove(&records[ix]);
+        index.insert(&record);
+        records[ix] = record;
+        self.records_changed();
+        true
+    }
+
+    /// Sets an attribute on every record with the id.
+    pub fn set_attr(&mut self, id: usize, name: &str, value: Attribute) -> bool {
+        let Self { records, ids, .. } = self;
+        let ixs = match ids.get(&id) {
+            Some(ixs) => ixs,
+            None      => return false,
+        };
+        for &ix in ixs {
+            records[ix].set_attr(name, value.clone());
+        }
+        self.records_changed();
+        true
+    }
+
+    fn take_ix(ids: &mut HashMap<usize, Vec<usize>>, id: usize) -> Option<usize> {
+        let ixs = ids.get_mut(&id)?;
+        let ix  = ixs.remove(0);
+        if ixs.is_empty() {
+            ids.remove(&id);
+        }
+        Some(ix)
     }
 
     pub fn clear(&mut self) {
         self.records.clear();
+        self.ids.clear();
         self.next_ix = 0;
+        self.index.clear();
+        self.records_changed();
+    }
+
+    /// Drops top records of the empty query and moves to the next version, see `self.version`.
+    fn records_changed(&mut self) {
+        *self.top_ixs.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+        self.version += 1;
+    }
+
+    /// A buffer for trigram counts of a search, return it with `give_counts` when done.
+    pub fn take_counts(&self) -> Vec<usize> {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner).pop().unwrap_or_default()
+    }
+
+    pub fn give_counts(&self, counts: Vec<usize>) {
+        self.counts.lock().unwrap_or_else(PoisonError::into_inner).push(counts);
+    }
+
+    pub fn add_field(&mut self, name: &str, weight: f64) -> usize {
+        self.fields.push(Field::new(name, weight));
+        self.fields.len() - 1
+    }
+
+    /// Adds a language for records besides `self.lang`, returns the index to set as `Record.lang`.
+    /// Index 0 stands for `self.lang`.
+    pub fn add_lang(&mut self, lang: Lang) -> usize {
+        self.langs.push(lang);
+        self.langs.len()
+    }
+
+    /// `None` for an index not returned by `add_lang`.
+    pub fn get_lang(&self, lang_ix: usize) -> Option<&Lang> {
+        match lang_ix {
+            0  => Some(&self.lang),
+            ix => self.langs.get(ix - 1),
+        }
+    }
+
+    /// Language of a stored record or a query of `tokenize_query`, their indexes are checked on the way in.
+    pub(crate) fn lang_at(&self, lang_ix: usize) -> &Lang {
+        debug_assert!(lang_ix <= self.langs.len(), "Invalid record language");
+        self.get_lang(lang_ix).unwrap_or(&self.lang)
+    }
+
+    pub fn all_langs(&self) -> impl Iterator<Item=&Lang> {
+        Some(&self.lang).into_iter().chain(self.langs.iter())
+    }
+
+    /// Index of the store language detected in the text, see `detect_lang`.
+    pub fn detect_lang(&self, text: &str) -> Option<usize> {
+        let code = detect_lang(text)?;
+        self.all_langs().position(|lang| lang.code() == code)
+    }
+
+    /// The query tokenized under every store language, in the order of language indices.
+    /// With `self.detect` a query in a known store language is tokenized under that one only,
+    /// repeated for every language, so records of any language are matched with it.
+    pub fn tokenize_query(&self, query: &str) -> Vec<TextOwn> {
+        let detected = if self.detect { self.detect_lang(query) } else { None };
+        if let Some(lang_ix) = detected {
+            return vec![tokenize_query(query, self.lang_at(lang_ix)); self.langs.len() + 1];
+        }
+        self.all_langs()
+            .map(|lang| tokenize_query(query, lang))
+            .collect()
+    }
+
+    pub fn field_weight(&self, field_ix: usize) -> f64 {
+        self.fields
+            .get(field_ix)
+            .map(|f| f.weight)
+            .unwrap_or(DEFAULT_FIELD_WEIGHT)
     }
 
     pub fn highlight_with(&mut self, dividers: (&str, &str)) {
         let left:  Vec<char> = to_vec(dividers.0);
         let right: Vec<char> = to_vec(dividers.1);
         self.dividers = (left, right);
+        self.config_changed();
+    }
+
+    /// Drops cached results and moves to the next generation, see `self.generation`.
+    /// Call it after changing anything but records that affects search.
+    pub fn config_changed(&mut self) {
+        self.cache.get_mut().unwrap_or_else(PoisonError::into_inner).clear();
+        self.generation += 1;
     }
 
     pub fn dividers<'a>(&'a self) -> (&'a [char], &'a [char]) {
         (&self.dividers.0, &self.dividers.1)
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use crate::tokenization::tokenize_query;
+    use crate::lang::{lang_english, lang_russian};
+    use super::{Store, Record, DuplicatePolicy};
+
+    fn get_store() -> Store {
+        let mut store = Store::new();
+        store.add(Record::new(10, "brown plush bear",     10, &store.lang));
+        store.add(Record::new(20, "the metal detector",   20, &store.lang));
+        store.add(Record::new(30, "yellow metal mailbox", 30, &store.lang));
+        store.add(Record::new(40, "thesaurus",            40, &store.lang));
+        store
+    }
+
+    fn search_ids(store: &Store, query: &str) -> Vec<usize> {
+        let query = tokenize_query(query, &store.lang);
+        store.search(&query.to_ref(), None).iter().map(|r| r.id).collect()
+    }
+
+    #[test]
+    fn remove_middle() {
+        let mut store = get_store();
+        let removed   = store.remove(20);
+        assert_eq!(removed.map(|r| r.id), Some(20));
+        assert_eq!(store.next_ix, 3);
+        for (ix, record) in store.records.iter().enumerate() {
+            assert_eq!(record.ix, ix);
+        }
+        assert_eq!(search_ids(&store, "metal"), vec![30]);
+        assert_eq!(search_ids(&store, "thesaurus"), vec![40]);
+        assert_eq!(search_ids(&store, ""), vec![40, 30, 10]);
+    }
+
+    #[test]
+    fn remove_missing() {
+        let mut store = get_store();
+        assert!(store.remove(50).is_none());
+        assert_eq!(store.records.len(), 4);
+    }
+
+    #[test]
+    fn update_title() {
+        let mut store = get_store();
+        let updated   = Record::new(20, "red wooden mailbox", 50, &store.lang);
+        assert!(store.update(updated));
+        assert_eq!(store.records[1].ix, 1);
+        assert_eq!(search_ids(&store, "detector"), Vec::<usize>::new());
+        assert_eq!(search_ids(&store, "mailbox"), vec![20, 30]);
+        assert_eq!(search_ids(&store, "")[0], 20);
+    }
+
+    #[test]
+    fn update_missing() {
+        let mut store = get_store();
+        let updated   = Record::new(50, "red wooden mailbox", 50, &store.lang);
+        assert!(!store.update(updated));
+        assert_eq!(search_ids(&store, "wooden"), Vec::<usize>::new());
+    }
+
+    #[test]
+    fn clear_index() {
+        let mut store = get_store();
+        store.clear();
+        store.add(Record::new(50, "wi-fi router", 50, &store.lang));
+        assert_eq!(search_ids(&store, "metal"), Vec::<usize>::new());
+        assert_eq!(search_ids(&store, ""), vec![50]);
+    }
+
+    #[test]
+    fn get_by_id() {
+        let mut store = get_store();
+        assert_eq!(store.get(30).map(|r| r.title_string()), Some("yellow metal mailbox".to_string()));
+        assert!(store.get(50).is_none());
+        store.remove(10);
+        assert_eq!(store.get(40).map(|r| r.ix), Some(0));
+        assert!(store.get(10).is_none());
+    }
+
+    #[test]
+    fn add_duplicate_reject() {
+        let mut store = get_store();
+        store.duplicates = DuplicatePolicy::Reject;
+        assert!(!store.add(Record::new(20, "red wooden mailbox", 50, &store.lang)));
+        assert_eq!(store.records.len(), 4);
+        assert_eq!(store.get(20).map(|r| r.rating), Some(20));
+    }
+
+    #[test]
+    fn add_duplicate_replace() {
+        let mut store = get_store();
+        store.duplicates = DuplicatePolicy::Replace;
+        assert!(store.add(Record::new(20, "red wooden mailbox", 50, &store.lang)));
+        assert_eq!(store.records.len(), 4);
+        assert_eq!(store.get(20).map(|r| r.rating), Some(50));
+        assert_eq!(search_ids(&store, "wooden"), vec![20]);
+    }
+
+    #[test]
+    fn add_duplicate_keep_both() {
+        let mut store = get_store();
+        store.duplicates = DuplicatePolicy::KeepBoth;
+        assert!(store.add(Record::new(20, "red wooden mailbox", 50, &store.lang)));
+        assert_eq!(store.records.len(), 5);
+        assert_eq!(search_ids(&store, "mailbox"), vec![20, 30]);
+        assert_eq!(search_ids(&store, "detector"), vec![20]);
+        store.remove(20);
+        store.remove(20);
+        assert!(store.get(20).is_none());
+        assert_eq!(search_ids(&store, "mailbox"), vec![30]);
+    }
+
+    #[test]
+    fn detect_query_lang() {
+        let mut store = Store::new();
+        store.lang = lang_english();
+        let ru = store.add_lang(lang_russian());
+        assert_eq!(store.detect_lang("brush for teeth"), Some(0));
+        assert_eq!(store.detect_lang("зубная щётка"),    Some(ru));
+        assert_eq!(store.detect_lang("der Herr"),        None);
+        assert_eq!(store.tokenize_query("зубная щётка").len(), 2);
+        store.detect = true;
+        let queries = store.tokenize_query("зубная щётка");
+        assert_eq!(queries.len(), 2);
+        assert_eq!(queries[0].to_ref(), queries[ru].to_ref());
+        assert_eq!(store.tokenize_query("toothbrush").len(), 2);
+    }
+}
@@ -1,13 +1,51 @@
 use fnv::{FnvHashMap as HashMap};
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+use rayon::prelude::*;
 use crate::utils::{Trigrams, LimitSort};
 use crate::tokenization::{Word, TextRef};
-use super::Record;
+use super::{Record, Field, DEFAULT_FIELD_WEIGHT};
 
 
+/// Records counted by one thread with the `parallel` feature.
+#[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+const COUNT_CHUNK: usize = 4096;
+
+
+/// Count a query trigram found in a record title adds, in fields it is scaled by the field weight.
+pub const GRAM_WEIGHT: usize = 100;
+
+
+/// Every record counting any trigram of a query, to count a query sharing most of them faster.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Candidates {
+    /// Sorted trigrams of the query.
+    pub grams: Vec<[char; 3]>,
+    /// Sorted ixs of records having any of `grams`.
+    pub ixs:   Vec<usize>,
+}
+
+
+impl Candidates {
+    pub fn new(mut grams: Vec<[char; 3]>, counts: &[usize]) -> Self {
+        grams.sort_unstable();
+        let ixs = counts
+            .iter()
+            .enumerate()
+            .filter(|&(_, &count)| count > 0)
+            .map(|(ix, _)| ix)
+            .collect();
+        Self { grams, ixs }
+    }
+}
+
+
+/// Postings of record trigrams, separate for the title and every field,
+/// so trigrams of heavier fields count more when picking candidates.
 pub struct TrigramIndex {
-    len:     usize,
-    dict:    HashMap<[char; 3], Vec<usize>>,
-    counts:  Vec<usize>,
+    pub len:    usize,
+    pub dict:   HashMap<[char; 3], Vec<usize>>,
+    /// Postings of field trigrams, in the order of record fields.
+    pub fields: Vec<HashMap<[char; 3], Vec<usize>>>,
 }
 
 
@@ -16,64 +54,231 @@ impl TrigramIndex {
         Self {
             len:    0,
             dict:   HashMap::default(),
-            counts: Vec::new(),
+            fields: Vec::new(),
         }
     }
 
     pub fn add(&mut self, record: &Record) {
-        let Self { dict, len, .. } = self;
-        let Record { ix, title, .. } = record;
-        let grams = Self::collect_grams(&title.to_ref());
-        *len += 1;
-        for &gram in grams.iter() {
-            dict
-                .entry(gram)
-                .and_modify(|ixs| {
-                    debug_assert!(
-                        ixs.len() == 0 || ixs.last().unwrap() < ix,
-                        "Gram ixs are not monotonously increasing"
-                    );
-                    ixs.push(*ix);
-                })
-                .or_insert_with(|| vec![*ix]);
+        let ix = record.ix;
+        self.len += 1;
+        for (slot, grams) in Self::collect_record_grams(record).into_iter().enumerate() {
+            let dict = self.dict_mut(slot);
+            for gram in grams {
+                dict
+                    .entry(gram)
+                    .and_modify(|ixs| {
+                        debug_assert!(
+                            ixs.len() == 0 || *ixs.last().unwrap() < ix,
+                            "Gram ixs are not monotonously increasing"
+                        );
+                        ixs.push(ix);
+                    })
+                    .or_insert_with(|| vec![ix]);
+            }
         }
     }
 
-    pub fn prepare(
-        &mut self,
-        query:   &TextRef,
-        size:    usize,
-    ) -> Vec<usize> {
-        let Self { counts, dict, .. } = self;
+    pub fn insert(&mut self, record: &Record) {
+        let ix = record.ix;
+        self.len += 1;
+        for (slot, grams) in Self::collect_record_grams(record).into_iter().enumerate() {
+            let dict = self.dict_mut(slot);
+            for gram in grams {
+                let ixs = dict.entry(gram).or_default();
+                if let Err(pos) = ixs.binary_search(&ix) {
+                    ixs.insert(pos, ix);
+                }
+            }
+        }
+    }
 
-        if query.words.len() == 0 {
-            return Vec::new();
+    pub fn remove(&mut self, record: &Record) {
+        let ix = record.ix;
+        debug_assert!(self.len > 0, "Removing from an empty index");
+        self.len -= 1;
+        for (slot, grams) in Self::collect_record_grams(record).into_iter().enumerate() {
+            let dict = self.dict_mut(slot);
+            for gram in grams.iter() {
+                let is_empty = match dict.get_mut(gram) {
+                    Some(ixs) => {
+                        if let Ok(pos) = ixs.binary_search(&ix) {
+                            ixs.remove(pos);
+                        }
+                        ixs.is_empty()
+                    },
+                    None => false,
+                };
+                if is_empty {
+                    dict.remove(gram);
+                }
+            }
         }
+    }
+
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.dict.clear();
+        self.fields.clear();
+    }
 
+    /// Slot 0 is the title, the following ones are fields.
+    fn dict_mut(&mut self, slot: usize) -> &mut HashMap<[char; 3], Vec<usize>> {
+        if slot == 0 {
+            return &mut self.dict;
+        }
+        if self.fields.len() < slot {
+            self.fields.resize_with(slot, HashMap::default);
+        }
+        &mut self.fields[slot - 1]
+    }
+
+    fn dicts(&self) -> impl Iterator<Item=&HashMap<[char; 3], Vec<usize>>> {
+        Some(&self.dict).into_iter().chain(self.fields.iter())
+    }
+
+    /// Sorted postings of the gram in the title and every field, with the count each posting adds.
+    fn postings<'a>(&'a self, gram: &'a [char; 3], units: &'a [usize]) -> impl Iterator<Item=(&'a [usize], usize)> + 'a {
+        self.dicts()
+            .zip(units.iter().copied())
+            .filter_map(move |(dict, unit)| dict.get(gram).map(|ixs| (&ixs[..], unit)))
+    }
+
+    /// Count of a gram found in the title and every field, missing field weights are the default one.
+    /// Fixed point weights of the title and each field, a zero weight field still counts a unit so its matches stay candidates.
+    fn units(&self, fields: &[Field]) -> Vec<usize> {
+        let weight = |field_ix: usize| fields.get(field_ix).map_or(DEFAULT_FIELD_WEIGHT, |f| f.weight);
+        let mut units = vec![GRAM_WEIGHT];
+        for field_ix in 0 .. self.fields.len() {
+            units.push((weight(field_ix) * GRAM_WEIGHT as f64).round().max(1.0) as usize);
+        }
+        units
+    }
+
+    /// Distinct trigrams of the query, `extra` texts count as well,
+    /// like synonyms or the query tokenized under other languages, so records having only a synonym are found.
+    pub fn query_grams(query: &TextRef, extra: &[TextRef]) -> Vec<[char; 3]> {
+        let mut grams = Self::collect_grams(query);
+        if !extra.is_empty() {
+            for text in extra {
+                grams.append(&mut Self::collect_grams(text));
+            }
+            grams.sort_unstable();
+            grams.dedup();
+        }
+        grams
+    }
+
+    /// Number of `grams` found in any text of the record.
+    pub fn record_grams(&self, grams: &[[char; 3]], ix: usize) -> usize {
+        grams
+            .iter()
+            .filter(|gram| {
+                self.dicts().any(|dict| matches!(dict.get(*gram), Some(ixs) if ixs.binary_search(&ix).is_ok()))
+            })
+            .count()
+    }
+
+    /// Top candidates by trigram count among records passing `accept`,
+    /// so restrictive filters don't eat up the candidate pool.
+    /// For paginated search `size` should cover the offset too.
+    /// Counts of grams found in fields are scaled by `fields` weights, see `GRAM_WEIGHT`.
+    /// Trigram counts of all records are left in `counts`, which the caller owns so the index can be shared.
+    /// With `within` candidates of an earlier query counts are the same, only fewer postings are scanned.
+    pub fn prepare(
+        &self,
+        grams:  &[[char; 3]],
+        fields: &[Field],
+        size:   usize,
+        accept: impl Fn(usize) -> bool,
+        within: Option<&Candidates>,
+        counts: &mut Vec<usize>,
+    ) -> Vec<usize> {
         counts.clear();
         counts.resize(self.len, 0);
+        if grams.is_empty() {
+            return Vec::new();
+        }
 
-        let grams = Self::collect_grams(&query);
+        let units = self.units(fields);
+        match within {
+            Some(within) => self.count_grams_within(grams, &units, within, counts),
+            None         => self.count_grams(grams, &units, counts),
+        }
 
+        counts
+            .iter()
+            .copied()
+            .enumerate()
+            .filter(|&(ix, count)| count > 0 && accept(ix))
+            .limit_sort_unstable(size.saturating_mul(10), |(_, count1), (_, count2)| count2.cmp(count1))
+            .map(|(ix, _)| ix)
+            .collect()
+    }
+
+    /// Records outside `within` have none of its grams, so postings of those are only looked up for `within` ixs,
+    /// past the previous one, and only postings of new grams are counted whole.
+    fn count_grams_within(&self, grams: &[[char; 3]], units: &[usize], within: &Candidates, counts: &mut [usize]) {
+        for gram in grams.iter() {
+            let known = within.grams.binary_search(gram).is_ok();
+            for (mut ixs, unit) in self.postings(gram, units) {
+                if !known {
+                    for &ix in ixs {
+                        counts[ix] += unit;
+                    }
+                    continue;
+                }
+                for &ix in within.ixs.iter() {
+                    ixs = &ixs[ixs.partition_point(|&other| other < ix) ..];
+                    if ixs.first() == Some(&ix) {
+                        counts[ix] += unit;
+                    }
+                }
+            }
+        }
+    }
+
+    #[cfg(not(all(feature = "parallel", not(target_arch = "wasm32"))))]
+    fn count_grams(&self, grams: &[[char; 3]], units: &[usize], counts: &mut [usize]) {
         for gram in grams.iter() {
-            if let Some(ixs) = dict.get(gram) {
+            for (ixs, unit) in self.postings(gram, units) {
                 for &ix in ixs {
                     unsafe {
-                        *counts.get_unchecked_mut(ix) += 1;
+                        *counts.get_unchecked_mut(ix) += unit;
                     }
                 }
             }
         }
+    }
 
+    /// Every thread counts its own range of records, picking it from sorted gram ixs.
+    #[cfg(all(feature = "parallel", not(target_arch = "wasm32")))]
+    fn count_grams(&self, grams: &[[char; 3]], units: &[usize], counts: &mut [usize]) {
         counts
-            .iter()
+            .par_chunks_mut(COUNT_CHUNK)
             .enumerate()
-            .filter(|(_, &count)| count > 0)
-            .limit_sort_unstable(size * 10, |(_, count1), (_, count2)| count2.cmp(count1))
-            .map(|(ix, _)| ix)
+            .for_each(|(chunk_ix, chunk)| {
+                let start = chunk_ix * COUNT_CHUNK;
+                let end   = start + chunk.len();
+                for gram in grams.iter() {
+                    for (ixs, unit) in self.postings(gram, units) {
+                        let from = ixs.partition_point(|&ix| ix < start);
+                        for &ix in ixs[from ..].iter().take_while(|&&ix| ix < end) {
+                            chunk[ix - start] += unit;
+                        }
+                    }
+                }
+            });
+    }
+
+    /// Grams of the title and every field.
+    fn collect_record_grams(record: &Record) -> Vec<Vec<[char; 3]>> {
+        record
+            .texts()
+            .map(|text| Self::collect_grams(&text.to_ref()))
             .collect()
     }
 
+    /// Phonetic keys are packed into grams too, so records sounding alike become candidates.
     fn collect_grams(text: &TextRef) -> Vec<[char; 3]> {
         let cap       = text.words.iter().map(|w| w.len()).sum::<usize>();
         let mut grams = Vec::with_capacity(cap);
@@ -82,6 +287,9 @@ impl TrigramIndex {
             for gram in chars.trigrams() {
                 grams.push(gram);
             }
+            if let Some(sound) = word.sound {
+                grams.push(sound.to_gram());
+            }
         }
         grams.sort_unstable();
         grams.dedup();
@@ -95,8 +303,12 @@ mod tests {
     use insta::{assert_debug_snapshot, assert_snapshot};
     use crate::lang::Lang;
     use crate::tokenization::tokenize_query;
-    use super::Record;
-    use super::TrigramIndex;
+    use super::{Record, Field};
+    use super::{TrigramIndex, Candidates, GRAM_WEIGHT};
+
+    fn query_grams(query: &str, lang: &Lang) -> Vec<[char; 3]> {
+        TrigramIndex::query_grams(&tokenize_query(query, lang).to_ref(), &[])
+    }
 
     fn get_index() -> (TrigramIndex, [Record; 5]) {
         let lang = Lang::new();
@@ -135,11 +347,9 @@ mod tests {
 
     fn check_prepare(name: &str, size: usize, queries: &[&str]) {
         let lang = Lang::new();
-        let (mut index, _) = get_index();
+        let (index, _) = get_index();
         for (i, query) in queries.iter().enumerate() {
-            let query = tokenize_query(query, &lang);
-            let query = query.to_ref();
-            let mut prepared = index.prepare(&query, size);
+            let mut prepared = index.prepare(&query_grams(query, &lang), &[], size, |_| true, None, &mut Vec::new());
             dbg!(&query);
             prepared.sort();
             assert_debug_snapshot!(format!("{}-{}", name, i), prepared);
@@ -167,6 +377,96 @@ mod tests {
         assert_snapshot!(export_dict(&index));
     }
 
+    #[test]
+    fn insert_middle() {
+        let (expected, _)  = get_index();
+        let (_, records)   = get_index();
+        let mut index      = TrigramIndex::new();
+        for ix in &[0, 2, 3, 1, 4] {
+            index.insert(&records[*ix]);
+        }
+        assert_eq!(index.len, expected.len);
+        assert_eq!(export_dict(&index), export_dict(&expected));
+    }
+
+    #[test]
+    fn remove_middle() {
+        let (mut index, records) = get_index();
+        index.remove(&records[1]);
+        assert_eq!(index.len, 4);
+        assert!(index.dict.values().all(|ixs| !ixs.contains(&1)));
+        assert!(!index.dict.contains_key(&['d', 'e', 't']));
+        assert_eq!(index.dict.get(&['m', 'e', 't']), Some(&vec![2]));
+    }
+
+    #[test]
+    fn remove_all() {
+        let (mut index, records) = get_index();
+        for record in records.iter() {
+            index.remove(record);
+        }
+        assert_eq!(index.len, 0);
+        assert!(index.dict.is_empty());
+    }
+
+    #[test]
+    fn prepare_many_records() {
+        let lang      = Lang::new();
+        let mut index = TrigramIndex::new();
+        for ix in 0 .. 10_000 {
+            let title      = if ix % 3 == 0 { "metal mailbox" } else { "plush bear" };
+            let mut record = Record::new(ix, title, 0, &lang);
+            record.ix = ix;
+            index.add(&record);
+        }
+        let grams      = query_grams("metal", &lang);
+        let mut counts = Vec::new();
+        let prepared   = index.prepare(&grams, &[], 10_000, |_| true, None, &mut counts);
+        assert_eq!(prepared.len(), 3334);
+        assert_eq!(counts.len(),   10_000);
+        assert_eq!((counts[4095], counts[4096], counts[9999]), (5 * GRAM_WEIGHT, 0, 5 * GRAM_WEIGHT));
+    }
+
+    #[test]
+    fn prepare_within() {
+        let lang       = Lang::new();
+        let (index, _) = get_index();
+        let grams      = query_grams("the", &lang);
+        let mut counts = Vec::new();
+        index.prepare(&grams, &[], 3, |_| true, None, &mut counts);
+        let within = Candidates::new(grams, &counts);
+        assert_eq!(within.ixs, vec![1, 3]);
+        for query in &["thes", "the metal"] {
+            let grams      = query_grams(query, &lang);
+            let prepared   = index.prepare(&grams, &[], 3, |_| true, None, &mut counts);
+            let all_counts = counts.clone();
+            assert_eq!(index.prepare(&grams, &[], 3, |_| true, Some(&within), &mut counts), prepared);
+            assert_eq!(counts, all_counts);
+        }
+    }
+
+    #[test]
+    fn prepare_field_weights() {
+        let lang      = Lang::new();
+        let mut index = TrigramIndex::new();
+        for ix in 0 .. 20 {
+            let (title, field) = if ix < 19 { ("metal mailbox", "") } else { ("plush bear", "mailbox") };
+            let mut record = Record::new(ix, title, 0, &lang);
+            record.add_field(field, &lang);
+            record.ix = ix;
+            index.add(&record);
+        }
+        let grams      = query_grams("mailbox", &lang);
+        let fields     = [Field::new("sku", 3.0)];
+        let mut counts = Vec::new();
+        let prepared   = index.prepare(&grams, &fields, 1, |_| true, None, &mut counts);
+        assert_eq!(prepared[0], 19);
+        assert_eq!(counts[19], counts[0] * 3);
+        assert_eq!(index.record_grams(&grams, 19), index.record_grams(&grams, 0));
+        let prepared = index.prepare(&grams, &[Field::new("sku", 0.0)], 1, |_| true, None, &mut counts);
+        assert!(!prepared.contains(&19));
+    }
+
     #[test]
     fn prepare_mismatch() {
         check_prepare("mismatch", 3, &["zzzap!"]);
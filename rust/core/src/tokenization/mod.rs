@@ -3,12 +3,14 @@ mod word_shape;
 mod word_split;
 mod word_view;
 mod text;
+mod synonyms;
 
 use crate::lang::{Lang, CharClass};
 pub use word::Word;
 pub use word_view::WordView;
 pub use word_shape::WordShape;
 pub use text::{Text, TextOwn, TextRef};
+pub use synonyms::{Synonym, tokenize_synonyms};
 
 
 pub fn tokenize_query(source: &str, lang: &Lang) -> TextOwn {
@@ -19,8 +21,10 @@ pub fn tokenize_query(source: &str, lang: &Lang) -> TextOwn {
         .strip(&[CharClass::NotAlphaNum], lang)
         .lower()
         .set_pos(lang)
-        .set_char_classes(lang)
         .set_stem(lang)
+        .transliterate(lang)
+        .set_sound(lang)
+        .set_char_classes(lang)
 }
 
 
@@ -31,6 +35,8 @@ pub fn tokenize_record(source: &str, lang: &Lang) -> TextOwn {
         .strip(&[CharClass::NotAlphaNum], lang)
         .lower()
         .set_pos(lang)
-        .set_char_classes(lang)
         .set_stem(lang)
+        .transliterate(lang)
+        .set_sound(lang)
+        .set_char_classes(lang)
 }
@@ -0,0 +1,81 @@
+use crate::lang::Lang;
+use super::{TextOwn, TextRef, tokenize_record};
+
+
+/// Alternative text for query words from `span.0` to `span.1` (exclusive).
+#[derive(Debug)]
+pub struct Synonym {
+    pub span: (usize, usize),
+    pub text: TextOwn,
+}
+
+
+pub fn tokenize_synonyms(query: &TextRef, lang: &Lang) -> Vec<Synonym> {
+    let mut synonyms = Vec::new();
+    let max_len = lang.synonym_len();
+    if max_len == 0 {
+        return synonyms;
+    }
+
+    let mut key = Vec::new();
+    for start in 0 .. query.words.len() {
+        key.clear();
+        for end in start + 1 ..= min!(start + max_len, query.words.len()) {
+            let word = &query.words[end - 1];
+            if end > start + 1 { key.push(' '); }
+            key.extend(&query.chars[word.slice.0 .. word.slice.1]);
+            for alternative in lang.get_synonyms(&key) {
+                synonyms.push(Synonym {
+                    span: (start, end),
+                    text: tokenize_record(alternative, lang),
+                });
+            }
+        }
+    }
+    synonyms
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::lang::{Lang, CYRILLIC_TO_LATIN};
+    use crate::tokenization::tokenize_query;
+    use super::tokenize_synonyms;
+
+    #[test]
+    fn tokenize_synonyms_phrases() {
+        let mut lang = Lang::new();
+        lang.add_synonym("tv", "television");
+        lang.add_synonym("tv stand", "media console");
+        let query    = tokenize_query("Big TV stand", &lang);
+        let synonyms = tokenize_synonyms(&query.to_ref(), &lang);
+        let received = synonyms
+            .iter()
+            .map(|s| (s.span, format!("{:?}", s.text)))
+            .collect::<Vec<_>>();
+        assert_eq!(received, vec![
+            ((1, 2), "Text { \"television\" }".to_string()),
+            ((1, 3), "Text { \"media\" \"console\" }".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn tokenize_synonyms_transliterated() {
+        let mut lang = Lang::new();
+        lang.add_synonym("щётка", "ёршик");
+        for (from, to) in CYRILLIC_TO_LATIN {
+            lang.add_transliteration(from, to);
+        }
+        lang.add_synonym("зубная паста", "гель");
+        let synonyms = |query: &str| {
+            let query = tokenize_query(query, &lang);
+            tokenize_synonyms(&query.to_ref(), &lang)
+                .iter()
+                .map(|s| s.span)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(synonyms("Щётка"),        vec![(0, 1)]);
+        assert_eq!(synonyms("shchetka"),     vec![(0, 1)]);
+        assert_eq!(synonyms("зубная паста"), vec![(0, 2)]);
+    }
+}
@@ -6,7 +6,7 @@ use super::word_shape::WordShape;
 use super::word_view::WordView;
 
 
-#[derive(PartialEq)]
+#[derive(PartialEq, Clone)]
 pub struct Text<W, T, C> where
     W: AsRef<[WordShape]>,
     T: AsRef<[char]>,
@@ -134,8 +134,19 @@ impl TextOwn {
     }
 
     pub fn set_stem(mut self, lang: &Lang) -> Self {
+        let mut buffer = String::new();
         for word in &mut self.words {
-            word.set_stem(&self.chars, lang);
+            word.set_stem(&self.chars, lang, &mut buffer);
+        }
+        self
+    }
+
+    pub fn set_sound(mut self, lang: &Lang) -> Self {
+        if lang.phonetic().is_none() {
+            return self;
+        }
+        for word in &mut self.words {
+            word.set_sound(&self.chars, lang);
         }
         self
     }
@@ -147,6 +158,27 @@ impl TextOwn {
         self
     }
 
+    /// Converts chars to the canonical script, padding source with `'\0'`
+    /// so highlighting still covers the original chars.
+    pub fn transliterate(mut self, lang: &Lang) -> Self {
+        let (positions, chars) = match lang.transliterate(&self.chars) {
+            Some(translit) => translit,
+            None => return self,
+        };
+        let mut source = vec!['\0'; chars.len()];
+        for (&ch, &position) in self.source.iter().zip(&positions) {
+            source[position] = ch;
+        }
+        for word in &mut self.words {
+            let (left, right) = word.slice;
+            word.stem  = positions[left + word.stem] - positions[left];
+            word.slice = (positions[left], positions[right]);
+        }
+        self.source = source;
+        self.chars  = chars;
+        self
+    }
+
     pub fn set_char_classes(mut self, lang: &Lang) -> Self {
         self.classes.resize(self.chars.len(), CharClass::Any);
         for (&ch, class) in &mut self.chars.iter().zip(&mut self.classes) {
@@ -254,9 +286,9 @@ mod tests {
         let chars = to_vec("-Foo- , Baz; ");
         let text  = Text {
                 words:  vec![
-                    WordShape { offset: 0, slice: (0,  5), stem: 5, pos: None, fin: true },  // "-Foo-"
-                    WordShape { offset: 1, slice: (6,  7), stem: 1, pos: None, fin: true },  // ","
-                    WordShape { offset: 2, slice: (8, 13), stem: 5, pos: None, fin: true },  // "Baz; "
+                    WordShape { offset: 0, slice: (0,  5), stem: 5, pos: None, sound: None, fin: true },  // "-Foo-"
+                    WordShape { offset: 1, slice: (6,  7), stem: 1, pos: None, sound: None, fin: true },  // ","
+                    WordShape { offset: 2, slice: (8, 13), stem: 5, pos: None, sound: None, fin: true },  // "Baz; "
                 ],
                 source:  chars.clone(),
                 chars:   chars.clone(),
@@ -273,8 +305,8 @@ mod tests {
         let chars = to_vec("-Foo- Baz; ");
         let text1 = Text {
                 words:  vec![
-                    WordShape { offset: 0, slice: (0, 5), stem: 5, pos: None, fin: true },  // "-Foo-"
-                    WordShape { offset: 1, slice: (5, 8), stem: 3, pos: None, fin: true },  // "Baz"
+                    WordShape { offset: 0, slice: (0, 5), stem: 5, pos: None, sound: None, fin: true },  // "-Foo-"
+                    WordShape { offset: 1, slice: (5, 8), stem: 3, pos: None, sound: None, fin: true },  // "Baz"
                 ],
                 source:  chars.clone(),
                 chars:   chars.clone(),
@@ -285,8 +317,8 @@ mod tests {
 
         let text2 = Text {
                 words:  vec![
-                    WordShape { offset: 0, slice: (0,  5), stem: 5, pos: None, fin: true },  // "-Foo-"
-                    WordShape { offset: 1, slice: (5, 10), stem: 5, pos: None, fin: true },  // "Baz; "
+                    WordShape { offset: 0, slice: (0,  5), stem: 5, pos: None, sound: None, fin: true },  // "-Foo-"
+                    WordShape { offset: 1, slice: (5, 10), stem: 5, pos: None, sound: None, fin: true },  // "Baz; "
                 ],
                 source:  chars.clone(),
                 chars:   chars.clone(),
@@ -304,9 +336,9 @@ mod tests {
         let chars = to_vec("Foo, Bar Baz");
         let text  = Text {
                 words:  vec![
-                    WordShape { offset: 0, slice: (0,  4), stem: 4, pos: None, fin: true }, // "Foo,"
-                    WordShape { offset: 1, slice: (5,  8), stem: 3, pos: None, fin: true }, // "Bar"
-                    WordShape { offset: 2, slice: (9, 12), stem: 3, pos: None, fin: true }, // "Baz"
+                    WordShape { offset: 0, slice: (0,  4), stem: 4, pos: None, sound: None, fin: true }, // "Foo,"
+                    WordShape { offset: 1, slice: (5,  8), stem: 3, pos: None, sound: None, fin: true }, // "Bar"
+                    WordShape { offset: 2, slice: (9, 12), stem: 3, pos: None, sound: None, fin: true }, // "Baz"
                 ],
                 source:  chars.clone(),
                 chars:   chars.clone(),
@@ -322,8 +354,8 @@ mod tests {
         let lang  = lang_english();
         let text  = Text {
                 words: vec![
-                    WordShape { offset: 0, slice: (0,  5), stem: 5, pos: None, fin: true }, // "hello"
-                    WordShape { offset: 1, slice: (6, 14), stem: 8, pos: None, fin: true }, // "universe"
+                    WordShape { offset: 0, slice: (0,  5), stem: 5, pos: None, sound: None, fin: true }, // "hello"
+                    WordShape { offset: 1, slice: (6, 14), stem: 8, pos: None, sound: None, fin: true }, // "universe"
                 ],
                 source:  chars.clone(),
                 chars:   chars.clone(),
@@ -339,8 +371,8 @@ mod tests {
         let lang  = lang_english();
         let text  = Text {
                 words: vec![
-                    WordShape { offset: 0, slice: (0,  3), stem: 3, pos: None, fin: true }, // "hello"
-                    WordShape { offset: 1, slice: (4, 12), stem: 8, pos: None, fin: true }, // "universe"
+                    WordShape { offset: 0, slice: (0,  3), stem: 3, pos: None, sound: None, fin: true }, // "hello"
+                    WordShape { offset: 1, slice: (4, 12), stem: 8, pos: None, sound: None, fin: true }, // "universe"
                 ],
                 source:  chars.clone(),
                 chars:   chars.clone(),
@@ -363,4 +395,19 @@ mod tests {
         let text  = Text::from_str("the universe, 123").set_char_classes(&lang);
         assert_debug_snapshot!(text.classes);
     }
+
+    #[test]
+    fn text_transliterate() {
+        let mut lang = Lang::new();
+        lang.add_transliteration("щ", "shch");
+        lang.add_transliteration("и", "i");
+        let text = Text::from_str("щи x")
+            .split(&[Whitespace], &lang)
+            .transliterate(&lang);
+        assert_eq!(text.chars,  to_vec("shchi x"));
+        assert_eq!(text.source, to_vec("щ\0\0\0и x"));
+        assert_eq!(text.words[0].slice, (0, 5));
+        assert_eq!(text.words[0].stem,  5);
+        assert_eq!(text.words[1].slice, (6, 7));
+    }
 }
@@ -1,4 +1,4 @@
-use crate::lang::{Lang, CharClass, CharPattern, PartOfSpeech};
+use crate::lang::{Lang, CharClass, CharPattern, PartOfSpeech, PhoneticKey};
 use super::word::Word;
 use super::word_view::WordView;
 use super::word_split::WordSplit;
@@ -11,6 +11,7 @@ pub struct WordShape {
     pub slice:  (usize, usize),
     pub stem:   usize,
     pub pos:    Option<PartOfSpeech>,
+    pub sound:  Option<PhoneticKey>,
     pub fin:    bool,
 }
 
@@ -31,6 +32,7 @@ impl WordShape {
             slice:  (0, len),
             stem:   len,
             pos:    None,
+            sound:  None,
             fin:    true,
         }
     }
@@ -49,6 +51,7 @@ impl WordShape {
             slice:  (self.slice.0, other.slice.1),
             stem:   other.slice.0 - self.slice.0 + other.stem,
             pos:    None,
+            sound:  None,
             fin:    other.fin,
         }
     }
@@ -78,9 +81,15 @@ impl WordShape {
         self
     }
 
-    pub fn set_stem(&mut self, chars: &[char], lang: &Lang) -> &mut Self {
+    pub fn set_stem(&mut self, chars: &[char], lang: &Lang, buffer: &mut String) -> &mut Self {
         let chars = &chars[self.slice.0 .. self.slice.1];
-        self.stem = lang.stem(chars);
+        self.stem = lang.stem(chars, buffer);
+        self
+    }
+
+    pub fn set_sound(&mut self, chars: &[char], lang: &Lang) -> &mut Self {
+        let chars = &chars[self.slice.0 .. self.slice.1];
+        self.sound = lang.phonetic_key(chars);
         self
     }
 
@@ -242,7 +251,7 @@ mod tests {
         let chars = to_vec("university");
         let lang  = lang_english();
         let mut word = WordShape::new(chars.len());
-        word.set_stem(&chars[..], &lang);
+        word.set_stem(&chars[..], &lang, &mut String::new());
         assert_eq!(word.stem, 7);
     }
 
@@ -55,6 +55,7 @@ impl<'a, 'b, P: CharPattern> Iterator for WordSplit<'a, 'b, P> {
             slice:  (word.slice.0 + *char_offset, word.slice.0 + *char_offset + len),
             stem:   len,
             pos:    None,
+            sound:  None,
             fin:    word.fin || *char_offset + len < word.len(),
         };
 
@@ -1,4 +1,4 @@
-use crate::lang::{CharClass, PartOfSpeech};
+use crate::lang::{CharClass, PartOfSpeech, PhoneticKey};
 use super::text::Text;
 use super::word::Word;
 use super::word_shape::WordShape;
@@ -10,6 +10,7 @@ pub struct WordView<'a> {
     pub slice:   (usize, usize),
     pub stem:    usize,
     pub pos:     Option<PartOfSpeech>,
+    pub sound:   Option<PhoneticKey>,
     pub fin:     bool,
     source:      &'a [char],
     chars:       &'a [char],
@@ -37,6 +38,7 @@ impl<'a> WordView<'a> {
             slice:   word.slice,
             stem:    word.stem,
             pos:     word.pos,
+            sound:   word.sound,
             fin:     word.fin,
             source:  &text.source.as_ref(),
             chars:   &text.chars.as_ref(),
@@ -50,6 +52,7 @@ impl<'a> WordView<'a> {
             slice:  self.slice,
             stem:   self.stem,
             pos:    self.pos,
+            sound:  self.sound,
             fin:    self.fin,
         }
     }
@@ -72,6 +75,7 @@ impl<'a> WordView<'a> {
             slice:   (self.slice.0, other.slice.1),
             stem:    other.slice.0 - self.slice.0 + other.stem,
             pos:     None,
+            sound:   None,
             fin:     other.fin,
             source:  &self.source,
             chars:   &self.chars,
@@ -28,7 +28,7 @@ fn init_store() {
                     val["title"].as_str().unwrap(),
                     val["rating"].as_u64().unwrap() as usize,
                     &store.lang,
-                ))
+                ));
             }
 
             cell.replace(Some(store));
@@ -80,7 +80,7 @@ fn assert_any_match(hits: &[SearchResult], pattern: &str) -> usize {
 fn ecommerce_joined_match() {
     using_store(|store| {
         let query = tokenize_query("night light", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<night> <light>");
         assert_any_match(&hits,    r"<nightlight>");
     });
@@ -91,7 +91,7 @@ fn ecommerce_joined_match() {
 fn ecommerce_unpopular_hit() {
     using_store(|store| {
         let query = tokenize_query("wise", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<wise>");
     });
 }
@@ -101,7 +101,7 @@ fn ecommerce_unpopular_hit() {
 fn ecommerce_longest_match() {
     using_store(|store| {
         let query = tokenize_query("i wise", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<wise>");
         assert_any_match(&hits,    r"<i>");
     });
@@ -112,7 +112,7 @@ fn ecommerce_longest_match() {
 fn ecommerce_match_surface() {
     using_store(|store| {
         let query = tokenize_query("it it ornament", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<ornament>");
         assert_hit_match(&hits[1], r"<it>.*<it>");
     });
@@ -126,7 +126,7 @@ fn ecommerce_match_surface() {
 fn ecommerce_case__little_bird() {
     using_store(|store| {
         let query = tokenize_query("little bird", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<little> <bird>");
         assert_all_match(&hits, r"<(little|bird)>")
     });
@@ -140,7 +140,7 @@ fn ecommerce_case__little_bird() {
 fn ecommerce_case__50s() {
     using_store(|store| {
         let query = tokenize_query("50s", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<50's>");
     });
 }
@@ -153,7 +153,7 @@ fn ecommerce_case__50s() {
 fn ecommerce_case__hold() {
     using_store(|store| {
         let query = tokenize_query("hold", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_all_match(&hits, r"<hold");
     });
 }
@@ -166,7 +166,7 @@ fn ecommerce_case__hold() {
 fn ecommerce_case__backp() {
     using_store(|store| {
         let query = tokenize_query("backp", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_all_match(&hits[..5], r"<backp>ack");
     });
 }
@@ -179,7 +179,7 @@ fn ecommerce_case__backp() {
 fn ecommerce_case__chop() {
     using_store(|store| {
         let query = tokenize_query("chop", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_all_match(&hits[..5], r"<chop>ping");
     });
 }
@@ -192,7 +192,7 @@ fn ecommerce_case__chop() {
 fn ecommerce_case__pop_corn() {
     using_store(|store| {
         let query = tokenize_query("pop corn", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_all_match(&hits[..2], r"<popcorn>");
     });
 }
@@ -208,7 +208,7 @@ fn ecommerce_case__orna() {
         for len in 3 .. query.chars().count() {
             let query = query.chars().take(len).collect::<String>();
             let query = tokenize_query(&query, &store.lang);
-            let hits  = store.search(&query.to_ref());
+            let hits  = store.search(&query.to_ref(), None);
             assert_hit_match(&hits[0], r"<orn");
         }
     });
@@ -222,11 +222,11 @@ fn ecommerce_case__orna() {
 fn ecommerce_case__vnt() {
     using_store(|store| {
         let query = tokenize_query("vn", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<vin>");
 
         let query = tokenize_query("vnt", &store.lang);
-        let hits  = store.search(&query.to_ref());
+        let hits  = store.search(&query.to_ref(), None);
         assert_hit_match(&hits[0], r"<vint>age");
     });
 }
@@ -241,7 +241,7 @@ fn ecommerce_case__vnt() {
 // fn ecommerce_case__greenpink() {
 //     using_store(|store| {
 //         let query = tokenize_query("greenpink", &store.lang);
-//         let hits  = store.search(&query.to_ref());
+//         let hits  = store.search(&query.to_ref(), None);
 //         dbg!(&hits);
 //         // assert_hit_match(&hits[0], r"<vin>");
 //     });
@@ -257,7 +257,7 @@ fn ecommerce_case__vnt() {
 // fn ecommerce_case__bauble() {
 //     using_store(|store| {
 //         let query = tokenize_query("babule", &store.lang);
-//         let hits  = store.search(&query.to_ref());
+//         let hits  = store.search(&query.to_ref(), None);
 //         dbg!(&hits);
 //         // assert_all_match(&hits[..5], r"<bauble>");
 //     });
@@ -1,10 +1,38 @@
 use wasm_bindgen::prelude::*;
+use js_sys::{Error, Reflect};
 use lucid_suggest_core as core;
 
 
+/// Empty `lang_code` means the bundle default language.
+/// Languages are included with `lang-*` cargo features, all of them by default.
 #[wasm_bindgen]
-pub fn create_store(id: usize) {
-    core::create_store(id, get_lang());
+pub fn create_store(id: usize, lang_code: &str) -> Result<(), JsValue> {
+    let lang = get_lang_by_code(lang_code)
+        .ok_or_else(|| invalid_argument(&format!("Unknown language {:?}", lang_code)))?;
+    core::create_store(id, lang).map_err(to_js_error)
+}
+
+
+fn get_lang_by_code(code: &str) -> Option<core::Lang> {
+    match code {
+        ""   => Some(get_lang()),
+        #[cfg(feature = "lang-de")] "de" => Some(core::lang_german()),
+        #[cfg(feature = "lang-en")] "en" => Some(core::lang_english()),
+        #[cfg(feature = "lang-es")] "es" => Some(core::lang_spanish()),
+        #[cfg(feature = "lang-fr")] "fr" => Some(core::lang_french()),
+        #[cfg(feature = "lang-pt")] "pt" => Some(core::lang_portuguese()),
+        #[cfg(feature = "lang-ru")] "ru" => Some(core::lang_russian()),
+        _    => None,
+    }
+}
+
+
+/// Builds the store language from a spec in the format of `LangSpec::parse`
+/// instead of the one compiled into the bundle.
+#[wasm_bindgen]
+pub fn create_store_with_spec(id: usize, spec: &str) -> Result<(), JsValue> {
+    let spec = core::LangSpec::parse(spec).map_err(to_js_error)?;
+    core::create_store(id, spec.to_lang()).map_err(to_js_error)
 }
 
 
@@ -25,45 +53,499 @@ pub fn create_store(id: usize) {
 
 
 #[wasm_bindgen]
-pub fn destroy_store(id: usize) {
-    core::destroy_store(id);
+pub fn destroy_store(id: usize) -> Result<(), JsValue> {
+    core::destroy_store(id).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn highlight_with(store_id: usize, left: &str, right: &str) -> Result<(), JsValue> {
+    core::highlight_with(store_id, (left, right)).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_limit(id: usize, limit: usize) -> Result<(), JsValue> {
+    core::set_limit(id, limit).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_duplicate_policy(store_id: usize, policy: &str) -> Result<(), JsValue> {
+    let policy = match policy {
+        "reject"    => core::DuplicatePolicy::Reject,
+        "replace"   => core::DuplicatePolicy::Replace,
+        "keep_both" => core::DuplicatePolicy::KeepBoth,
+        _           => return Err(invalid_argument(&format!("Unknown duplicate policy {:?}", policy))),
+    };
+    core::set_duplicate_policy(store_id, policy).map_err(to_js_error)
+}
+
+
+/// Score names are passed joined with \0, weights match `weighted` names.
+#[wasm_bindgen]
+pub fn set_ranking(store_id: usize, order: &str, weighted: &str, weights: Vec<f64>) -> Result<(), JsValue> {
+    let order    = parse_score_types(order)?;
+    let weighted = parse_score_types(weighted)?;
+    if weighted.len() != weights.len() {
+        return Err(invalid_argument("Score weights count mismatch"));
+    }
+    let weights = weighted.into_iter().zip(weights).collect();
+    core::set_ranking(store_id, core::RankingConfig { order, weights }).map_err(to_js_error)
+}
+
+
+fn parse_score_types(names: &str) -> Result<Vec<core::ScoreType>, JsValue> {
+    split_fields(names)
+        .into_iter()
+        .map(|name| {
+            core::ScoreType::from_name(name)
+                .ok_or_else(|| invalid_argument(&format!("Unknown score {:?}", name)))
+        })
+        .collect()
+}
+
+
+/// Parameter names are passed joined with \0, their values in the same order.
+#[wasm_bindgen]
+pub fn set_matching(store_id: usize, typos: bool, prefix_only: bool, names: &str, values: Vec<f64>) -> Result<(), JsValue> {
+    let names = split_fields(names);
+    if names.len() != values.len() {
+        return Err(invalid_argument("Match parameter values count mismatch"));
+    }
+    let mut matching = core::MatchConfig { typos, prefix_only, ..core::MatchConfig::default() };
+    for (name, value) in names.into_iter().zip(values) {
+        if !matching.set_param(name, value) {
+            return Err(invalid_argument(&format!("Unknown match parameter {:?}", name)));
+        }
+    }
+    core::set_matching(store_id, matching).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_explain(store_id: usize, explain: bool) -> Result<(), JsValue> {
+    core::set_explain(store_id, explain).map_err(to_js_error)
+}
+
+
+/// Attribute names are passed joined with \0.
+#[wasm_bindgen]
+pub fn set_facets(store_id: usize, names: &str) -> Result<(), JsValue> {
+    core::set_facets(store_id, &split_fields(names)).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn add_synonym(store_id: usize, from: &str, to: &str) -> Result<(), JsValue> {
+    core::add_synonym(store_id, from, to).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn add_transliteration(store_id: usize, from: &str, to: &str) -> Result<(), JsValue> {
+    core::add_transliteration(store_id, from, to).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn transliterate_cyrillic(store_id: usize) -> Result<(), JsValue> {
+    core::transliterate_cyrillic(store_id).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_phonetic(store_id: usize, enable: bool) -> Result<(), JsValue> {
+    core::set_phonetic(store_id, enable).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_key_typos(store_id: usize, enable: bool) -> Result<(), JsValue> {
+    core::set_key_typos(store_id, enable).map_err(to_js_error)
+}
+
+
+/// Group phrases are passed joined with \0.
+#[wasm_bindgen]
+pub fn add_synonyms(store_id: usize, group: &str) -> Result<(), JsValue> {
+    core::add_synonyms(store_id, &split_fields(group)).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn add_field(store_id: usize, name: &str, weight: f64) -> Result<usize, JsValue> {
+    core::add_field(store_id, name, weight).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_detect_lang(store_id: usize, detect: bool) -> Result<(), JsValue> {
+    core::set_detect_lang(store_id, detect).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_incremental(store_id: usize, enable: bool) -> Result<(), JsValue> {
+    core::set_incremental(store_id, enable).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_cache_size(store_id: usize, size: usize) -> Result<(), JsValue> {
+    core::set_cache_size(store_id, size).map_err(to_js_error)
+}
+
+
+/// Hits and misses of the result cache.
+#[wasm_bindgen]
+pub fn get_cache_stats(store_id: usize) -> Result<Vec<usize>, JsValue> {
+    let (hits, misses) = core::cache_stats(store_id).map_err(to_js_error)?;
+    Ok(vec![hits, misses])
+}
+
+
+#[wasm_bindgen]
+pub fn set_layout_retry(store_id: usize, min_hits: usize) -> Result<(), JsValue> {
+    core::set_layout_retry(store_id, min_hits).map_err(to_js_error)
+}
+
+
+/// Returns the index to pass as `lang` to `add_record`.
+#[wasm_bindgen]
+pub fn add_lang(store_id: usize, lang_code: &str) -> Result<usize, JsValue> {
+    let lang = get_lang_by_code(lang_code)
+        .ok_or_else(|| invalid_argument(&format!("Unknown language {:?}", lang_code)))?;
+    core::add_lang(store_id, lang).map_err(to_js_error)
 }
 
 
+/// Fails with a `DUPLICATE_RECORD` error if the record is rejected as a duplicate.
 #[wasm_bindgen]
-pub fn highlight_with(store_id: usize, left: &str, right: &str) {
-    core::highlight_with(store_id, (left, right));
+pub fn add_record(store_id: usize, record_id: usize, title: &str, fields: &str, rating: usize, lang: Option<usize>) -> Result<(), JsValue> {
+    core::add_record(store_id, record_id, title, &split_values(fields), rating, lang).map_err(to_js_error)
 }
 
 
 #[wasm_bindgen]
-pub fn set_limit(id: usize, limit: usize)  {
-    core::set_limit(id, limit);
+pub fn get_record_title(store_id: usize, record_id: usize) -> Result<Option<String>, JsValue> {
+    core::get_record(store_id, record_id, |record| {
+        record.map(|r| r.title_string())
+    }).map_err(to_js_error)
 }
 
 
 #[wasm_bindgen]
-pub fn add_record(store_id: usize, record_id: usize, title: &str, rating: usize) {
-    core::add_record(store_id, record_id, title, rating);
+pub fn get_record_rating(store_id: usize, record_id: usize) -> Result<Option<usize>, JsValue> {
+    core::get_record(store_id, record_id, |record| {
+        record.map(|r| r.rating)
+    }).map_err(to_js_error)
 }
 
 
 #[wasm_bindgen]
-pub fn run_search(store_id: usize, query: &str) {
-    core::run_search(store_id, query)
+pub fn remove_record(store_id: usize, record_id: usize) -> Result<(), JsValue> {
+    core::remove_record(store_id, record_id).map_err(to_js_error)
 }
 
 
 #[wasm_bindgen]
-pub fn get_result_ids(store_id: usize) -> Vec<usize> {
+pub fn update_record(store_id: usize, record_id: usize, title: &str, fields: &str, rating: usize, lang: Option<usize>) -> Result<(), JsValue> {
+    core::update_record(store_id, record_id, title, &split_values(fields), rating, lang).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_int_attr(store_id: usize, record_id: usize, name: &str, value: f64) -> Result<bool, JsValue> {
+    core::set_record_attr(store_id, record_id, name, core::Attribute::Int(value as i64)).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn set_keywords_attr(store_id: usize, record_id: usize, name: &str, keywords: &str) -> Result<bool, JsValue> {
+    let keywords = split_fields(keywords);
+    core::set_record_attr(store_id, record_id, name, core::Attribute::keywords(&keywords)).map_err(to_js_error)
+}
+
+
+/// A JS `Error` named after the Rust error type, with `code` telling its variants apart, e.g. `DUPLICATE_RECORD`.
+fn to_js_error<E: ErrorCode + std::fmt::Display>(err: E) -> JsValue {
+    js_error(err.name(), err.code(), &err.to_string())
+}
+
+
+/// Malformed arguments the core API doesn't know about, like an unknown language code.
+fn invalid_argument(message: &str) -> JsValue {
+    js_error("ArgumentError", "INVALID_ARGUMENT", message)
+}
+
+
+fn js_error(name: &str, code: &str, message: &str) -> JsValue {
+    let err = Error::new(message);
+    err.set_name(name);
+    // Setting a property of a fresh object can't fail.
+    let _ = Reflect::set(&err, &JsValue::from_str("code"), &JsValue::from_str(code));
+    err.into()
+}
+
+
+trait ErrorCode {
+    fn name(&self) -> &'static str;
+    fn code(&self) -> &'static str;
+}
+
+
+impl ErrorCode for core::StoreError {
+    fn name(&self) -> &'static str {
+        "StoreError"
+    }
+
+    fn code(&self) -> &'static str {
+        match self {
+            core::StoreError::UnknownStore(_)    => "UNKNOWN_STORE",
+            core::StoreError::DuplicateStore(_)  => "DUPLICATE_STORE",
+            core::StoreError::DuplicateRecord(_) => "DUPLICATE_RECORD",
+            core::StoreError::UnknownRecord(_)   => "UNKNOWN_RECORD",
+            core::StoreError::InvalidLimit(_)    => "INVALID_LIMIT",
+            core::StoreError::UnknownLang(_)     => "UNKNOWN_LANG",
+            core::StoreError::Snapshot(_)        => "INVALID_SNAPSHOT",
+        }
+    }
+}
+
+
+impl ErrorCode for core::LangSpecError {
+    fn name(&self) -> &'static str {
+        "LangSpecError"
+    }
+
+    fn code(&self) -> &'static str {
+        "INVALID_LANG_SPEC"
+    }
+}
+
+
+impl ErrorCode for core::FilterError {
+    fn name(&self) -> &'static str {
+        "FilterError"
+    }
+
+    fn code(&self) -> &'static str {
+        "INVALID_FILTER"
+    }
+}
+
+
+/// Names are passed joined with \0, an empty string means no names.
+fn split_fields(fields: &str) -> Vec<&str> {
+    if fields.is_empty() {
+        Vec::new()
+    } else {
+        fields.split('\0').collect()
+    }
+}
+
+
+/// Record field values are passed joined with \0, one per field added by `add_field`,
+/// so an empty string is a single empty value.
+fn split_values(values: &str) -> Vec<&str> {
+    values.split('\0').collect()
+}
+
+
+#[wasm_bindgen]
+pub fn export_store(store_id: usize) -> Result<Vec<u8>, JsValue> {
+    core::export_store(store_id).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn import_store(store_id: usize, bytes: &[u8]) -> Result<(), JsValue> {
+    core::import_store(store_id, bytes).map_err(to_js_error)
+}
+
+
+/// An empty filter string means no filter.
+#[wasm_bindgen]
+pub fn run_search(store_id: usize, query: &str, filter: &str, offset: usize, limit: usize) -> Result<(), JsValue> {
+    let filter = match filter {
+        "" => None,
+        _  => Some(core::Filter::parse(filter).map_err(to_js_error)?),
+    };
+    core::run_search_page(store_id, query, filter.as_ref(), offset, limit).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn get_result_ids(store_id: usize) -> Result<Vec<usize>, JsValue> {
     core::using_results(store_id, |results| {
         results.iter().map(|r| r.id).collect()
-    })
+    }).map_err(to_js_error)
 }
 
 
+/// Keyboard layout corrections of results, each terminated with \0, empty if the hit matched the query as typed.
 #[wasm_bindgen]
-pub fn get_result_titles(store_id: usize) -> String {
+pub fn get_result_corrections(store_id: usize) -> Result<String, JsValue> {
+    core::using_results(store_id, |results| {
+        let mut corrections = String::new();
+        for result in results.iter() {
+            if let Some(correction) = &result.correction {
+                corrections.push_str(correction);
+            }
+            corrections.push('\0');
+        }
+        corrections
+    }).map_err(to_js_error)
+}
+
+
+/// Highlighted UTF-16 ranges of result titles, each result as a count followed by start, end pairs.
+#[wasm_bindgen]
+pub fn get_result_spans(store_id: usize) -> Result<Vec<usize>, JsValue> {
+    core::using_results(store_id, |results| {
+        let mut spans = Vec::new();
+        for result in results.iter() {
+            push_spans(&mut spans, &result.spans);
+        }
+        spans
+    }).map_err(to_js_error)
+}
+
+
+/// Same as `get_result_spans`, for every store field of every result, empty for fields a record lacks.
+#[wasm_bindgen]
+pub fn get_result_field_spans(store_id: usize) -> Result<Vec<usize>, JsValue> {
+    core::using_results(store_id, |results| {
+        let mut spans = Vec::new();
+        for result in results.iter() {
+            for field_spans in result.field_spans.iter() {
+                push_spans(&mut spans, field_spans);
+            }
+        }
+        spans
+    }).map_err(to_js_error)
+}
+
+
+fn push_spans(buffer: &mut Vec<usize>, spans: &[core::HighlightSpan]) {
+    buffer.push(spans.len());
+    for span in spans {
+        buffer.push(span.utf16.0);
+        buffer.push(span.utf16.1);
+    }
+}
+
+
+/// A JSON array with an explanation object or null per result.
+#[wasm_bindgen]
+pub fn get_result_explanations(store_id: usize) -> Result<String, JsValue> {
+    core::using_results(store_id, |results| {
+        let mut json = String::from("[");
+        for (i, result) in results.iter().enumerate() {
+            if i > 0 { json.push(','); }
+            match &result.explain {
+                Some(explain) => write_explanation(&mut json, explain),
+                None          => json.push_str("null"),
+            }
+        }
+        json.push(']');
+        json
+    }).map_err(to_js_error)
+}
+
+
+fn write_explanation(json: &mut String, explain: &core::Explanation) {
+    json.push_str("{\"scores\":{");
+    for (i, (name, score)) in explain.scores.iter().enumerate() {
+        if i > 0 { json.push(','); }
+        json.push_str(&format!("\"{}\":{}", name, score));
+    }
+    json.push_str("},\"rmatches\":");
+    write_matches(json, &explain.rmatches);
+    json.push_str(",\"qmatches\":");
+    write_matches(json, &explain.qmatches);
+    json.push_str(",\"fields\":{");
+    for (i, field) in explain.fields.iter().enumerate() {
+        if i > 0 { json.push(','); }
+        write_string(json, &field.name);
+        json.push_str(&format!(":{{\"weight\":{},\"rmatches\":", field.weight));
+        write_matches(json, &field.rmatches);
+        json.push_str(",\"qmatches\":");
+        write_matches(json, &field.qmatches);
+        json.push('}');
+    }
+    json.push('}');
+    json.push_str(&format!(",\"grams\":{},\"candidates\":{}}}", explain.grams, explain.candidates));
+}
+
+
+fn write_matches(json: &mut String, matches: &[core::MatchExplanation]) {
+    json.push('[');
+    for (i, m) in matches.iter().enumerate() {
+        if i > 0 { json.push(','); }
+        json.push_str("{\"word\":");
+        write_string(json, &m.word);
+        json.push_str(&format!(
+            ",\"len\":{},\"typos\":{},\"func\":{},\"fin\":{}}}",
+            m.len, m.typos, m.func, m.fin,
+        ));
+    }
+    json.push(']');
+}
+
+
+fn write_string(json: &mut String, string: &str) {
+    json.push('"');
+    for ch in string.chars() {
+        match ch {
+            '"'  => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            ch if (ch as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch   => json.push(ch),
+        }
+    }
+    json.push('"');
+}
+
+
+/// Facet names and values are \0-terminated, one per count.
+#[wasm_bindgen]
+pub fn get_facet_names(store_id: usize) -> Result<String, JsValue> {
+    core::using_facets(store_id, |facets| {
+        let mut names = String::new();
+        for facet in facets.iter() {
+            names.push_str(&facet.name);
+            names.push('\0');
+        }
+        names
+    }).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn get_facet_values(store_id: usize) -> Result<String, JsValue> {
+    core::using_facets(store_id, |facets| {
+        let mut values = String::new();
+        for facet in facets.iter() {
+            values.push_str(&facet.value);
+            values.push('\0');
+        }
+        values
+    }).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn get_facet_counts(store_id: usize) -> Result<Vec<usize>, JsValue> {
+    core::using_facets(store_id, |facets| {
+        facets.iter().map(|f| f.count).collect()
+    }).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn get_result_titles(store_id: usize) -> Result<String, JsValue> {
     core::using_results(store_id, |results| {
         let bytelen: usize = results.iter()
             .map(|result| result.title.len())
@@ -74,5 +556,24 @@ pub fn get_result_titles(store_id: usize) -> String {
             concat.push('\0');
         }
         concat
-    })
+    }).map_err(to_js_error)
+}
+
+
+#[wasm_bindgen]
+pub fn get_result_fields(store_id: usize) -> Result<String, JsValue> {
+    core::using_results(store_id, |results| {
+        let bytelen: usize = results.iter()
+            .flat_map(|result| result.fields.iter())
+            .map(|field| field.len() + 1)
+            .sum();
+        let mut concat = String::with_capacity(bytelen);
+        for result in results {
+            for field in &result.fields {
+                concat.push_str(field);
+                concat.push('\0');
+            }
+        }
+        concat
+    }).map_err(to_js_error)
 }